@@ -0,0 +1,125 @@
+use std::fmt;
+
+// What solving one part of a day comes out as
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum Answer {
+    Int(i64),
+    Text(String),
+    // e.g. letters drawn in pixels, a row at a time with true for lit
+    Bitmap(Vec<Vec<bool>>),
+}
+
+impl From<i64> for Answer {
+    fn from(n: i64) -> Self {
+        Answer::Int(n)
+    }
+}
+
+impl From<i32> for Answer {
+    fn from(n: i32) -> Self {
+        Answer::Int(n as i64)
+    }
+}
+
+impl From<usize> for Answer {
+    fn from(n: usize) -> Self {
+        Answer::Int(n as i64)
+    }
+}
+
+impl From<String> for Answer {
+    fn from(s: String) -> Self {
+        Answer::Text(s)
+    }
+}
+
+impl From<Vec<Vec<bool>>> for Answer {
+    fn from(rows: Vec<Vec<bool>>) -> Self {
+        Answer::Bitmap(rows)
+    }
+}
+
+// Quoted and escaped
+pub fn json_string(s: &str) -> String {
+    let mut out = String::with_capacity(s.len() + 2);
+    out.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            c if (c as u32) < 0x20 => out.push_str(&format!("\\u{:04x}", c as u32)),
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+    out
+}
+
+impl Answer {
+    // The kind and answer fields of a JSON object, e.g. "kind":"int","answer":42; bitmaps
+    // are a list of rows of 0s and 1s
+    pub fn json_fields(&self) -> String {
+        let (kind, answer) = match self {
+            Answer::Int(n) => ("int", n.to_string()),
+            Answer::Text(s) => ("text", json_string(s)),
+            Answer::Bitmap(rows) => {
+                let rows = rows
+                    .iter()
+                    .map(|row| {
+                        let pixels = row.iter().map(|&lit| (lit as u8).to_string());
+                        format!("[{}]", pixels.collect::<Vec<_>>().join(","))
+                    })
+                    .collect::<Vec<_>>();
+                ("bitmap", format!("[{}]", rows.join(",")))
+            }
+        };
+        format!("\"kind\":\"{}\",\"answer\":{}", kind, answer)
+    }
+}
+
+impl fmt::Display for Answer {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Answer::Int(n) => write!(f, "{}", n),
+            Answer::Text(s) => write!(f, "{}", s),
+            Answer::Bitmap(rows) => {
+                let rows = rows
+                    .iter()
+                    .map(|row| {
+                        row.iter()
+                            .map(|&lit| if lit { '#' } else { ' ' })
+                            .collect::<String>()
+                    })
+                    .collect::<Vec<_>>();
+                write!(f, "{}", rows.join("\n"))
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn json() {
+        assert_eq!(
+            Answer::Int(-7).json_fields(),
+            "\"kind\":\"int\",\"answer\":-7"
+        );
+        assert_eq!(
+            Answer::Text("say \"hi\"\n\\".to_string()).json_fields(),
+            "\"kind\":\"text\",\"answer\":\"say \\\"hi\\\"\\n\\\\\""
+        );
+
+        let bitmap = Answer::Bitmap(vec![vec![true, false], vec![false, true]]);
+        assert_eq!(
+            bitmap.json_fields(),
+            "\"kind\":\"bitmap\",\"answer\":[[1,0],[0,1]]"
+        );
+        assert_eq!(bitmap.to_string(), "# \n #");
+    }
+}
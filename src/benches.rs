@@ -0,0 +1,183 @@
+// Timings for comparing implementation choices, run by hand with e.g.
+//
+//   cargo test --release benches -- --ignored --nocapture
+//
+// They're ignored by default because they're slow in debug builds and only the numbers
+// are interesting.
+
+use std::time::Instant;
+
+use crate::day15;
+use crate::intcode::memory::{DenseMemory, Memory, SparseMemory};
+use crate::intcode::profile::StepCount;
+use crate::intcode::{str_to_ints, RunResult, VM};
+
+const RUNS: u32 = 40;
+
+// Runs f once, returning its result and how long it took, in ms
+fn time<T>(f: &mut impl FnMut() -> T) -> (T, f64) {
+    let start = Instant::now();
+    let result = f();
+    (result, start.elapsed().as_secs_f64() * 1000.0)
+}
+
+// Alternates between the two, so noise from the machine hits both the same, and reports
+// the fastest time for each. Which goes first swaps every run too, so neither always
+// gets caches warmed up by the other.
+// Prints how many times faster the new one is
+fn compare<T: PartialEq>(
+    name: &str,
+    (old_name, mut old): (&str, impl FnMut() -> T),
+    (new_name, mut new): (&str, impl FnMut() -> T),
+) {
+    let mut old_ms = f64::INFINITY;
+    let mut new_ms = f64::INFINITY;
+
+    for run in 0..RUNS {
+        let ((old_result, old_run), (new_result, new_run)) = if run % 2 == 0 {
+            let old_timed = time(&mut old);
+            (old_timed, time(&mut new))
+        } else {
+            let new_timed = time(&mut new);
+            (time(&mut old), new_timed)
+        };
+        old_ms = old_ms.min(old_run);
+        new_ms = new_ms.min(new_run);
+
+        assert!(old_result == new_result);
+    }
+
+    println!(
+        "{}: {} {:.1} ms, {} {:.1} ms ({:.2}x)",
+        name,
+        old_name,
+        old_ms,
+        new_name,
+        new_ms,
+        old_ms / new_ms
+    );
+}
+
+// Reads and writes just past the end of the program, like a stack; this is where the
+// backends differ, without the rest of the VM diluting it
+fn stack_accesses<M: Memory>(code: &[i64]) -> i64 {
+    let mut memory = M::from_program(code);
+    let mut total = 0;
+    for i in 0..1_000_000 {
+        let address = code.len() + (i * 7) % 100;
+        total += memory.get(address);
+        memory.set(address, total & 0xff);
+    }
+    total
+}
+
+fn run_9b<M: Memory>(mut vm: VM<M>) -> Vec<i64> {
+    vm.give_input(2);
+    assert_eq!(vm.run(), Ok(RunResult::Stopped));
+    vm.get_all_outputs()
+}
+
+#[test]
+#[ignore]
+fn memory_backends() {
+    let code = str_to_ints(include_str!("resources/9a.txt"));
+    compare(
+        "memory only",
+        ("sparse", || stack_accesses::<SparseMemory>(&code)),
+        ("dense", || stack_accesses::<DenseMemory>(&code)),
+    );
+    compare(
+        "9b",
+        ("sparse", || {
+            run_9b(VM::with_memory(SparseMemory::from_program(&code)))
+        }),
+        ("dense", || {
+            run_9b(VM::with_memory(DenseMemory::from_program(&code)))
+        }),
+    );
+
+    // never touches memory past the program, so both backends do the same work here
+    let code = str_to_ints(include_str!("resources/15a.txt"));
+    compare(
+        "15",
+        ("sparse", || {
+            day15::explore(
+                VM::with_memory(SparseMemory::from_program(&code)),
+                &StepCount::new(),
+            )
+        }),
+        ("dense", || {
+            day15::explore(
+                VM::with_memory(DenseMemory::from_program(&code)),
+                &StepCount::new(),
+            )
+        }),
+    );
+}
+
+// Every noun and verb, like day 2 part b but without stopping at the answer
+fn run_2b(new_vm: impl Fn(&[i64]) -> VM) -> i64 {
+    let mut code = str_to_ints(include_str!("resources/2a.txt"));
+    let mut total = 0;
+    for noun in 0..100 {
+        for verb in 0..100 {
+            code[1] = noun;
+            code[2] = verb;
+            let mut vm = new_vm(&code);
+            if vm.run() == Ok(RunResult::Stopped) {
+                total += vm.peek(0);
+            }
+        }
+    }
+    total
+}
+
+// Plays day 13's game to the end, keeping the paddle under the ball
+fn run_13b(new_vm: impl Fn(&[i64]) -> VM) -> i64 {
+    let mut code = str_to_ints(include_str!("resources/13a.txt"));
+    code[0] = 2;
+    let mut vm = new_vm(&code);
+
+    let (mut ball, mut paddle, mut score) = (0, 0, 0);
+    loop {
+        let result = vm.run();
+        let outputs = vm.get_all_outputs();
+        for tile in outputs.chunks(3) {
+            match tile {
+                [-1, 0, val] => score = *val,
+                [x, _, 4] => ball = *x,
+                [x, _, 3] => paddle = *x,
+                _ => {}
+            }
+        }
+
+        match result {
+            Ok(RunResult::NeedInput) => vm.give_input((ball - paddle).signum()),
+            Ok(RunResult::Stopped) => return score,
+            other => panic!("Day 13 gave {:?}", other),
+        }
+    }
+}
+
+#[test]
+#[ignore]
+fn decode_cache() {
+    let uncached = |code: &[i64]| VM::new(code).without_decode_cache();
+
+    let code = str_to_ints(include_str!("resources/9a.txt"));
+    compare(
+        "9b",
+        ("uncached", || run_9b(uncached(&code))),
+        ("cached", || run_9b(VM::new(&code))),
+    );
+    compare(
+        "13b",
+        ("uncached", || run_13b(uncached)),
+        ("cached", || run_13b(VM::new)),
+    );
+    compare(
+        "2b",
+        ("uncached", || run_2b(uncached)),
+        ("cached", || run_2b(VM::new)),
+    );
+}
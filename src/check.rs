@@ -0,0 +1,235 @@
+use std::collections::HashMap;
+use std::fmt;
+use std::panic;
+use std::time::Instant;
+
+use crate::answer::{json_string, Answer};
+use crate::solution::{Part, Solution, StepCount};
+
+// Running parts on their own inputs and checking the answers against ones known to be
+// right, reported as a table row or a JSON object per part
+
+// Answers known to be right, a line per part like "8a: 2413"; newlines in an answer are
+// written as \n
+pub const EXPECTED_ANSWERS: &str = include_str!("resources/answers.txt");
+
+pub fn parse_expected(text: &str) -> HashMap<String, String> {
+    text.lines()
+        .filter_map(|line| {
+            // pictures can start with a space, so only the one after the colon goes
+            let (number, answer) = line.split_once(':')?;
+            let answer = answer.strip_prefix(' ').unwrap_or(answer).trim_end();
+            Some((number.trim().to_string(), answer.to_string()))
+        })
+        .collect()
+}
+
+// As written in the answers file; pictures' rows often end in spaces, which editors trim
+pub fn answer_line(answer: &Answer) -> String {
+    let text = answer.to_string();
+    let rows = text.lines().map(str::trim_end).collect::<Vec<_>>();
+    rows.join("\\n")
+}
+
+#[derive(Debug, Eq, PartialEq, Copy, Clone)]
+pub enum Status {
+    Ok,
+    // nothing to check it against
+    Unchecked,
+    Wrong,
+    Panicked,
+}
+
+impl fmt::Display for Status {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name = match self {
+            Status::Ok => "ok",
+            Status::Unchecked => "unchecked",
+            Status::Wrong => "wrong",
+            Status::Panicked => "panicked",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+// How running one part went
+#[derive(Clone, Debug, PartialEq)]
+pub struct Outcome {
+    pub day: u32,
+    pub part: Part,
+    // or what it panicked with
+    pub answer: Result<Answer, String>,
+    pub elapsed_ms: f64,
+    pub expected: Option<String>,
+}
+
+// Heads the columns of table_row
+pub fn table_header() -> String {
+    format!(
+        "{:>3}  {:4}  {:<20}  {:>10}  Status",
+        "Day", "Part", "Answer", "Time"
+    )
+}
+
+impl Outcome {
+    // Runs the part on its own input, catching any panic; the panic hook still prints
+    // it, unless the caller replaces that
+    pub fn run(solution: &dyn Solution, part: Part, expected: &HashMap<String, String>) -> Self {
+        let number = format!("{}{}", solution.day(), part);
+
+        let start = Instant::now();
+        // solutions don't keep any state, so a panic can't leave one broken
+        let solve =
+            panic::AssertUnwindSafe(|| solution.solve(part, solution.input(), &StepCount::new()));
+        let answer = panic::catch_unwind(solve);
+        let elapsed_ms = start.elapsed().as_secs_f64() * 1000.0;
+
+        let answer = answer.map_err(|payload| {
+            payload
+                .downcast_ref::<&str>()
+                .map(|s| s.to_string())
+                .or_else(|| payload.downcast_ref::<String>().cloned())
+                .unwrap_or_default()
+        });
+
+        Outcome {
+            day: solution.day(),
+            part,
+            answer,
+            elapsed_ms,
+            expected: expected.get(&number).cloned(),
+        }
+    }
+
+    pub fn status(&self) -> Status {
+        match (&self.answer, &self.expected) {
+            (Err(_), _) => Status::Panicked,
+            (Ok(_), None) => Status::Unchecked,
+            (Ok(answer), Some(expected)) if answer_line(answer) == *expected => Status::Ok,
+            (Ok(_), Some(_)) => Status::Wrong,
+        }
+    }
+
+    // A panic only counts as a failure if there's an answer it should have given
+    pub fn failed(&self) -> bool {
+        match self.status() {
+            Status::Wrong => true,
+            Status::Panicked => self.expected.is_some(),
+            _ => false,
+        }
+    }
+
+    pub fn table_row(&self) -> String {
+        let shown = match &self.answer {
+            Err(_) => "-".to_string(),
+            Ok(Answer::Bitmap(_)) => "(picture)".to_string(),
+            Ok(answer) => answer.to_string(),
+        };
+        let status = match (&self.answer, &self.expected, self.status()) {
+            (Err(message), _, _) => format!("panicked: {}", message),
+            (Ok(_), Some(expected), Status::Wrong) => format!("WRONG, expected {}", expected),
+            (_, _, status) => status.to_string(),
+        };
+        format!(
+            "{:>3}  {:4}  {:<20}  {:>7.1} ms  {}",
+            self.day, self.part, shown, self.elapsed_ms, status
+        )
+    }
+
+    // One object, with error for panics and expected for wrong answers
+    pub fn json(&self) -> String {
+        let status = self.status();
+        let mut line = format!("{{\"day\":{},\"part\":\"{}\",", self.day, self.part);
+        match &self.answer {
+            Ok(answer) => line.push_str(&answer.json_fields()),
+            Err(_) => line.push_str("\"kind\":null,\"answer\":null"),
+        }
+        line.push_str(&format!(
+            ",\"elapsed_ms\":{:.3},\"status\":\"{}\"",
+            self.elapsed_ms, status
+        ));
+        match (&self.answer, &self.expected) {
+            (Err(message), _) => line.push_str(&format!(",\"error\":{}", json_string(message))),
+            (Ok(_), Some(expected)) if status == Status::Wrong => {
+                line.push_str(&format!(",\"expected\":{}", json_string(expected)))
+            }
+            _ => (),
+        }
+        line.push('}');
+        line
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::day01::Day01;
+
+    fn outcome(answer: Result<Answer, String>, expected: Option<&str>) -> Outcome {
+        Outcome {
+            day: 8,
+            part: Part::B,
+            answer,
+            elapsed_ms: 1.5,
+            expected: expected.map(str::to_string),
+        }
+    }
+
+    #[test]
+    fn expected_answers() {
+        let expected = parse_expected("1a: 3\n 8b:  # \\n##\n\nnot an answer\n");
+        assert_eq!(expected.len(), 2);
+        assert_eq!(expected["1a"], "3");
+        assert_eq!(expected["8b"], " # \\n##");
+
+        let picture = Answer::Bitmap(vec![vec![false, true, false], vec![true, true, false]]);
+        assert_eq!(answer_line(&picture), " #\\n##");
+        assert!(parse_expected(EXPECTED_ANSWERS).contains_key("16a"));
+    }
+
+    #[test]
+    fn statuses() {
+        let right = outcome(Ok(Answer::Int(42)), Some("42"));
+        assert_eq!(right.status(), Status::Ok);
+        assert!(!right.failed());
+        assert_eq!(
+            right.table_row(),
+            "  8  b     42                        1.5 ms  ok"
+        );
+        assert_eq!(
+            right.json(),
+            "{\"day\":8,\"part\":\"b\",\"kind\":\"int\",\"answer\":42,\"elapsed_ms\":1.500,\"status\":\"ok\"}"
+        );
+
+        let wrong = outcome(Ok(Answer::Int(41)), Some("42"));
+        assert!(wrong.failed());
+        assert!(wrong.table_row().ends_with("WRONG, expected 42"));
+        assert!(wrong
+            .json()
+            .ends_with("\"status\":\"wrong\",\"expected\":\"42\"}"));
+
+        // panics only count against parts with a known answer
+        let panicked = outcome(Err("oops".to_string()), None);
+        assert_eq!(panicked.status(), Status::Panicked);
+        assert!(!panicked.failed());
+        assert!(panicked
+            .table_row()
+            .ends_with("-                         1.5 ms  panicked: oops"));
+        assert!(panicked.json().contains("\"kind\":null,\"answer\":null"));
+        assert!(panicked.json().ends_with("\"error\":\"oops\"}"));
+        assert!(outcome(Err(String::new()), Some("42")).failed());
+
+        assert_eq!(
+            outcome(Ok(Answer::Int(1)), None).status(),
+            Status::Unchecked
+        );
+    }
+
+    #[test]
+    fn run() {
+        let expected = parse_expected(EXPECTED_ANSWERS);
+        let outcome = Outcome::run(&Day01, Part::A, &expected);
+        assert_eq!((outcome.day, outcome.part), (1, Part::A));
+        assert_eq!(outcome.status(), Status::Ok);
+    }
+}
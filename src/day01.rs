@@ -1,7 +1,12 @@
-const DATA_1A: &str = include_str!("resources/1a.txt");
+use std::hint::black_box;
 
-fn get_ints() -> Vec<i64> {
-    DATA_1A
+use crate::answer::Answer;
+use crate::solution::{Solution, StepCount};
+
+pub const INPUT: &str = include_str!("resources/1a.txt");
+
+fn get_ints(input: &str) -> Vec<i64> {
+    input
         .lines()
         .filter(|line| line.len() > 0)
         .map(|line| line.parse::<i64>().unwrap())
@@ -31,14 +36,42 @@ fn total_fuel_cost(weight: i64) -> i64 {
     }
 }
 
-pub fn a() {
-    let total: i64 = get_ints().into_iter().map(fuel_cost).sum();
+pub fn solve_a(input: &str) -> Answer {
+    let total: i64 = get_ints(input).into_iter().map(fuel_cost).sum();
+
+    total.into()
+}
+
+pub fn solve_b(input: &str) -> Answer {
+    let total: i64 = get_ints(input).into_iter().map(total_fuel_cost).sum();
 
-    println!("1A: {}", total);
+    total.into()
 }
 
-pub fn b() {
-    let total: i64 = get_ints().into_iter().map(total_fuel_cost).sum();
+pub struct Day01;
+
+impl Solution for Day01 {
+    fn day(&self) -> u32 {
+        1
+    }
+
+    fn name(&self) -> &'static str {
+        "The Tyranny of the Rocket Equation"
+    }
+
+    fn input(&self) -> &'static str {
+        INPUT
+    }
+
+    fn parse(&self, input: &str) {
+        black_box(get_ints(input));
+    }
+
+    fn a(&self, input: &str, _steps: &StepCount) -> Answer {
+        solve_a(input)
+    }
 
-    println!("1B: {}", total);
+    fn b(&self, input: &str, _steps: &StepCount) -> Answer {
+        solve_b(input)
+    }
 }
@@ -1,71 +1,170 @@
-const DATA_2A: &str = include_str!("resources/2a.txt");
-
-fn get_ints() -> Vec<i64> {
-    DATA_2A
-        .split(',')
-        .filter(|token| token.len() > 0)
-        .map(|token| token.trim().parse::<i64>().unwrap())
-        .collect::<Vec<i64>>()
-}
+use std::hint::black_box;
 
-fn run_program(data: &mut Vec<i64>) {
-    let mut ip = 0;
-    loop {
-        let op_code = data[ip];
+use crate::answer::Answer;
+use crate::intcode::patch::Patch;
+use crate::intcode::{str_to_ints, RunResult, VM};
+use crate::solution::{Solution, StepCount};
 
-        match op_code {
-            1 => {
-                let a = data[data[ip + 1] as usize];
-                let b = data[data[ip + 2] as usize];
+pub const INPUT: &str = include_str!("resources/2a.txt");
 
-                let result_register = data[ip + 3] as usize;
+// Runs with the given noun and verb in addresses 1 and 2, and returns what's left in
+// address 0; None if the program doesn't halt cleanly
+fn run_program(code: &[i64], noun: i64, verb: i64, steps: &StepCount) -> Option<i64> {
+    let mut vm = VM::new(code).with_patch(&Patch::new().with(1, noun).with(2, verb));
+    let result = vm.run();
+    steps.add(vm.total_steps());
+    match result {
+        Ok(RunResult::Stopped) => Some(vm.peek(0)),
+        _ => None,
+    }
+}
 
-                data[result_register] = a + b;
-                ip += 4;
-            }
-            2 => {
-                let a = data[data[ip + 1] as usize];
-                let b = data[data[ip + 2] as usize];
+pub fn solve_a(input: &str, steps: &StepCount) -> Answer {
+    let code = str_to_ints(input);
 
-                let result_register = data[ip + 3] as usize;
+    run_program(&code, 12, 2, steps)
+        .expect("The program should halt")
+        .into()
+}
 
-                data[result_register] = a * b;
-                ip += 4;
-            }
-            99 => return,
-            _ => {
-                panic!("Unrecognized opcode {}", op_code);
+pub fn solve_b(input: &str, steps: &StepCount) -> Answer {
+    let code = str_to_ints(input);
+
+    for noun in 0..100 {
+        for verb in 0..100 {
+            if run_program(&code, noun, verb, steps) == Some(19690720) {
+                return (100 * noun + verb).into();
             }
         }
     }
+
+    panic!("No noun/verb gave the correct answer");
 }
 
-pub fn a() {
-    let mut data = get_ints();
-    data[1] = 12;
-    data[2] = 2;
+pub struct Day02;
 
-    run_program(&mut data);
+impl Solution for Day02 {
+    fn day(&self) -> u32 {
+        2
+    }
+
+    fn name(&self) -> &'static str {
+        "1202 Program Alarm"
+    }
 
-    println!("2a: {}", data[0]);
+    fn input(&self) -> &'static str {
+        INPUT
+    }
+
+    fn parse(&self, input: &str) {
+        black_box(str_to_ints(input));
+    }
+
+    fn a(&self, input: &str, steps: &StepCount) -> Answer {
+        solve_a(input, steps)
+    }
+
+    fn b(&self, input: &str, steps: &StepCount) -> Answer {
+        solve_b(input, steps)
+    }
 }
 
-pub fn b() {
-    for noun in 0..100 {
-        for verb in 0..100 {
-            let mut data = get_ints();
-            data[1] = noun;
-            data[2] = verb;
+#[cfg(test)]
+mod tests {
+    use rand::rngs::StdRng;
+    use rand::{Rng, SeedableRng};
+
+    use super::*;
+    use crate::intcode::VmError;
+
+    // The interpreter day 2 had before it moved to the shared VM, kept to check the VM
+    // still agrees with it. Overflow gives None, where the VM reports an error.
+    fn reference_run(data: &mut [i64]) -> Option<()> {
+        let mut ip = 0;
+        loop {
+            let op_code = data[ip];
+
+            match op_code {
+                1 => {
+                    let a = data[data[ip + 1] as usize];
+                    let b = data[data[ip + 2] as usize];
+
+                    let result_register = data[ip + 3] as usize;
+
+                    data[result_register] = a.checked_add(b)?;
+                    ip += 4;
+                }
+                2 => {
+                    let a = data[data[ip + 1] as usize];
+                    let b = data[data[ip + 2] as usize];
 
-            run_program(&mut data);
+                    let result_register = data[ip + 3] as usize;
 
-            if data[0] == 19690720 {
-                let answer = 100 * noun + verb;
-                println!("2b: {}", answer);
-                return;
+                    data[result_register] = a.checked_mul(b)?;
+                    ip += 4;
+                }
+                99 => return Some(()),
+                _ => {
+                    panic!("Unrecognized opcode {}", op_code);
+                }
             }
         }
     }
 
-    panic!("No noun/verb gave the correct answer");
+    // Some adds and multiplies then a halt, followed by a few words of data. Operands
+    // read from anywhere, but results only go to the data, so the code is left alone.
+    fn random_program(rng: &mut StdRng) -> Vec<i64> {
+        let instructions = rng.gen_range(1, 12);
+        let data_start = instructions * 4 + 1;
+        let len = data_start + 8;
+
+        let mut program = Vec::with_capacity(len);
+        for _ in 0..instructions {
+            program.push(if rng.gen() { 1 } else { 2 });
+            program.push(rng.gen_range(0, len) as i64);
+            program.push(rng.gen_range(0, len) as i64);
+            program.push(rng.gen_range(data_start, len) as i64);
+        }
+        program.push(99);
+        while program.len() < len {
+            program.push(rng.gen_range(0, 5));
+        }
+        program
+    }
+
+    #[test]
+    fn vm_agrees_with_reference() {
+        let mut rng = StdRng::seed_from_u64(2019);
+        let mut completed = 0;
+        for _ in 0..2000 {
+            let program = random_program(&mut rng);
+
+            let mut expected = program.clone();
+            let mut vm = VM::new(&program);
+            match (reference_run(&mut expected), vm.run()) {
+                (Some(()), Ok(RunResult::Stopped)) => {
+                    let actual = vm.peek_range(0..program.len());
+                    assert_eq!(expected, actual, "Program {:?}", program);
+                    completed += 1;
+                }
+                (None, Err(VmError::Overflow { .. })) => (),
+                (expected, actual) => panic!(
+                    "Program {:?} gave {:?} from the reference and {:?} from the VM",
+                    program, expected, actual
+                ),
+            }
+        }
+
+        assert!(completed > 1000, "Only {} programs ran", completed);
+    }
+
+    #[test]
+    fn test_2a() {
+        assert_eq!(solve_a(INPUT, &StepCount::new()), Answer::Int(3790689));
+    }
+
+    #[test]
+    fn test_2b() {
+        assert_eq!(solve_b(INPUT, &StepCount::new()), Answer::Int(6533));
+    }
 }
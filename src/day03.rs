@@ -1,6 +1,10 @@
 use std::collections::{HashMap, HashSet};
+use std::hint::black_box;
 
-const DATA_3A: &str = include_str!("resources/3a.txt");
+use crate::answer::Answer;
+use crate::solution::{Solution, StepCount};
+
+pub const INPUT: &str = include_str!("resources/3a.txt");
 
 struct WireLayout(Vec<Move>);
 
@@ -59,16 +63,16 @@ fn make_wire_layout(text: &str) -> WireLayout {
     WireLayout(text.split(',').map(make_move).collect())
 }
 
-fn get_wires() -> Vec<WireLayout> {
-    DATA_3A
+fn get_wires(input: &str) -> Vec<WireLayout> {
+    input
         .lines()
         .filter(|line| line.len() > 0)
         .map(make_wire_layout)
         .collect()
 }
 
-pub fn a() {
-    let mut wires = get_wires();
+pub fn solve_a(input: &str) -> Answer {
+    let mut wires = get_wires(input);
 
     assert_eq!(wires.len(), 2);
 
@@ -103,11 +107,11 @@ pub fn a() {
         }
     }
 
-    println!("3a: {:?}", least_distance);
+    least_distance.unwrap().into()
 }
 
-pub fn b() {
-    let mut wires = get_wires();
+pub fn solve_b(input: &str) -> Answer {
+    let mut wires = get_wires(input);
 
     assert_eq!(wires.len(), 2);
 
@@ -146,5 +150,33 @@ pub fn b() {
         }
     }
 
-    println!("3b: {:?}", least_distance);
+    least_distance.unwrap().into()
+}
+
+pub struct Day03;
+
+impl Solution for Day03 {
+    fn day(&self) -> u32 {
+        3
+    }
+
+    fn name(&self) -> &'static str {
+        "Crossed Wires"
+    }
+
+    fn input(&self) -> &'static str {
+        INPUT
+    }
+
+    fn parse(&self, input: &str) {
+        black_box(get_wires(input));
+    }
+
+    fn a(&self, input: &str, _steps: &StepCount) -> Answer {
+        solve_a(input)
+    }
+
+    fn b(&self, input: &str, _steps: &StepCount) -> Answer {
+        solve_b(input)
+    }
 }
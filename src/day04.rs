@@ -1,10 +1,14 @@
 use std::collections::HashSet;
+use std::hint::black_box;
 
-const DATA_4A: &str = include_str!("resources/4a.txt");
+use crate::answer::Answer;
+use crate::solution::{Solution, StepCount};
+
+pub const INPUT: &str = include_str!("resources/4a.txt");
 const NUM_DIGITS: usize = 6;
 
-fn get_range() -> [usize; 2] {
-    let text = DATA_4A.split('-').collect::<Vec<_>>();
+fn get_range(input: &str) -> [usize; 2] {
+    let text = input.trim().split('-').collect::<Vec<_>>();
     assert_eq!(text.len(), 2);
 
     [
@@ -49,8 +53,8 @@ fn get_all_nondecreasing(
     }
 }
 
-pub fn a() {
-    let [start, end] = get_range();
+pub fn solve_a(input: &str) -> Answer {
+    let [start, end] = get_range(input);
 
     let mut all = HashSet::new();
 
@@ -58,7 +62,7 @@ pub fn a() {
 
     let count = all.into_iter().filter(|&n| n >= start && n <= end).count();
 
-    println!("4a: {}", count);
+    count.into()
 }
 
 fn has_good_pair(mut n: usize) -> bool {
@@ -72,8 +76,8 @@ fn has_good_pair(mut n: usize) -> bool {
     digit_counts.iter().any(|&n| n == 2)
 }
 
-pub fn b() {
-    let [start, end] = get_range();
+pub fn solve_b(input: &str) -> Answer {
+    let [start, end] = get_range(input);
 
     let mut all = HashSet::new();
 
@@ -84,5 +88,33 @@ pub fn b() {
         .filter(|&n| n >= start && n <= end && has_good_pair(n))
         .count();
 
-    println!("4b: {}", count);
+    count.into()
+}
+
+pub struct Day04;
+
+impl Solution for Day04 {
+    fn day(&self) -> u32 {
+        4
+    }
+
+    fn name(&self) -> &'static str {
+        "Secure Container"
+    }
+
+    fn input(&self) -> &'static str {
+        INPUT
+    }
+
+    fn parse(&self, input: &str) {
+        black_box(get_range(input));
+    }
+
+    fn a(&self, input: &str, _steps: &StepCount) -> Answer {
+        solve_a(input)
+    }
+
+    fn b(&self, input: &str, _steps: &StepCount) -> Answer {
+        solve_b(input)
+    }
 }
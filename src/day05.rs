@@ -1,47 +1,73 @@
-const DAY_5: &str = include_str!("resources/5a.txt");
+pub const INPUT: &str = include_str!("resources/5a.txt");
 
+use std::hint::black_box;
+
+use crate::answer::Answer;
 use crate::intcode::{str_to_ints, RunResult, VM};
+use crate::solution::{Solution, StepCount};
 
-fn do_a() -> i64 {
-    let program = str_to_ints(DAY_5);
+pub fn solve_a(input: &str, steps: &StepCount) -> Answer {
+    let program = str_to_ints(input);
 
     let mut vm = VM::new(&program);
     vm.give_input(1);
 
-    assert_eq!(vm.run(), RunResult::Stopped);
+    assert_eq!(vm.run(), Ok(RunResult::Stopped));
+    steps.add(vm.total_steps());
 
     let output = vm.get_all_outputs();
 
-    for i in 0..output.len() - 1 {
-        assert_eq!(output[i], 0);
+    for &val in &output[..output.len() - 1] {
+        assert_eq!(val, 0);
     }
 
-    output[output.len() - 1]
-}
-
-pub fn a() {
-    println!("5a: {}", do_a());
+    output[output.len() - 1].into()
 }
 
-fn do_b() -> i64 {
-    let program = str_to_ints(DAY_5);
+pub fn solve_b(input: &str, steps: &StepCount) -> Answer {
+    let program = str_to_ints(input);
 
     let mut vm = VM::new(&program);
     vm.give_input(5);
 
-    assert_eq!(vm.run(), RunResult::Stopped);
+    assert_eq!(vm.run(), Ok(RunResult::Stopped));
+    steps.add(vm.total_steps());
 
     let output = vm.get_all_outputs();
 
-    for i in 0..output.len() - 1 {
-        assert_eq!(output[i], 0);
+    for &val in &output[..output.len() - 1] {
+        assert_eq!(val, 0);
     }
 
-    output[output.len() - 1]
+    output[output.len() - 1].into()
 }
 
-pub fn b() {
-    println!("5b: {}", do_b());
+pub struct Day05;
+
+impl Solution for Day05 {
+    fn day(&self) -> u32 {
+        5
+    }
+
+    fn name(&self) -> &'static str {
+        "Sunny with a Chance of Asteroids"
+    }
+
+    fn input(&self) -> &'static str {
+        INPUT
+    }
+
+    fn parse(&self, input: &str) {
+        black_box(str_to_ints(input));
+    }
+
+    fn a(&self, input: &str, steps: &StepCount) -> Answer {
+        solve_a(input, steps)
+    }
+
+    fn b(&self, input: &str, steps: &StepCount) -> Answer {
+        solve_b(input, steps)
+    }
 }
 
 #[cfg(test)]
@@ -50,11 +76,11 @@ mod tests {
 
     #[test]
     pub fn check_5a() {
-        assert_eq!(do_a(), 16489636);
+        assert_eq!(solve_a(INPUT, &StepCount::new()), Answer::Int(16489636));
     }
 
     #[test]
     pub fn check_5b() {
-        assert_eq!(do_b(), 9386583);
+        assert_eq!(solve_b(INPUT, &StepCount::new()), Answer::Int(9386583));
     }
 }
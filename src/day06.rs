@@ -1,6 +1,10 @@
 use std::collections::HashMap;
+use std::hint::black_box;
 
-const DAY_6: &str = include_str!("resources/6a.txt");
+use crate::answer::Answer;
+use crate::solution::{Solution, StepCount};
+
+pub const INPUT: &str = include_str!("resources/6a.txt");
 
 struct GraphBuilder {
     // node name to node id
@@ -146,14 +150,14 @@ fn get_graph(text: &str) -> Graph {
     graph.build()
 }
 
-pub fn a() {
-    let graph = get_graph(DAY_6);
+pub fn solve_a(input: &str) -> Answer {
+    let graph = get_graph(input);
     let num_orbits = graph.get_num_trans_orbits();
-    println!("6a: {}", num_orbits);
+    num_orbits.into()
 }
 
-pub fn b() {
-    let graph = get_graph(DAY_6);
+pub fn solve_b(input: &str) -> Answer {
+    let graph = get_graph(input);
 
     let you = "YOU";
     let san = "SAN";
@@ -161,7 +165,35 @@ pub fn b() {
     // the -2 is for "moving to orbiting to the same thing" instead of moving you -> san
     let dist = graph.orbital_distance(you, san) - 2;
 
-    println!("6b: {}", dist);
+    dist.into()
+}
+
+pub struct Day06;
+
+impl Solution for Day06 {
+    fn day(&self) -> u32 {
+        6
+    }
+
+    fn name(&self) -> &'static str {
+        "Universal Orbit Map"
+    }
+
+    fn input(&self) -> &'static str {
+        INPUT
+    }
+
+    fn parse(&self, input: &str) {
+        black_box(get_graph(input));
+    }
+
+    fn a(&self, input: &str, _steps: &StepCount) -> Answer {
+        solve_a(input)
+    }
+
+    fn b(&self, input: &str, _steps: &StepCount) -> Answer {
+        solve_b(input)
+    }
 }
 
 #[cfg(test)]
@@ -1,7 +1,13 @@
-const DAY_8: &str = include_str!("resources/8a.txt");
+use std::hint::black_box;
+
+use crate::answer::Answer;
+use crate::solution::{Solution, StepCount};
+
+pub const INPUT: &str = include_str!("resources/8a.txt");
 
 fn to_image(data: &str, num_rows: usize, num_cols: usize) -> Image {
     let chars = data
+        .trim()
         .chars()
         .map(|c| c.to_digit(10).unwrap() as u8)
         .collect::<Vec<u8>>();
@@ -72,8 +78,8 @@ fn count_pixels(layer: &Layer, val: u8) -> usize {
 const WIDTH: usize = 25; // num_cols / row_length
 const HEIGHT: usize = 6; // num_rows / col_length
 
-pub fn a() {
-    let image = to_image(DAY_8, HEIGHT, WIDTH);
+pub fn solve_a(input: &str) -> Answer {
+    let image = to_image(input, HEIGHT, WIDTH);
 
     let mut fewest_zeros_so_far = None;
     let mut best_layer = None;
@@ -97,11 +103,11 @@ pub fn a() {
 
     let score = count_pixels(best_layer_ref, 1) * count_pixels(best_layer_ref, 2);
 
-    println!("8a: {}", score);
+    score.into()
 }
 
-pub fn b() {
-    let image = to_image(DAY_8, HEIGHT, WIDTH);
+pub fn solve_b(input: &str) -> Answer {
+    let image = to_image(input, HEIGHT, WIDTH);
 
     fn merge(top: u8, bot: u8) -> u8 {
         if top == 2 {
@@ -123,15 +129,38 @@ pub fn b() {
         }
     }
 
-    println!("8b is an image:");
-    println!();
-    for row in final_layer.iter() {
-        print!("  ");
-        for c in row.iter() {
-            let nice_char = if *c == 0 { ' ' } else { 'X' };
-            print!("{}", nice_char);
-        }
-        println!();
+    let rows = final_layer
+        .iter()
+        .map(|row| row.iter().map(|&c| c != 0).collect::<Vec<_>>())
+        .collect::<Vec<_>>();
+
+    rows.into()
+}
+
+pub struct Day08;
+
+impl Solution for Day08 {
+    fn day(&self) -> u32 {
+        8
+    }
+
+    fn name(&self) -> &'static str {
+        "Space Image Format"
+    }
+
+    fn input(&self) -> &'static str {
+        INPUT
+    }
+
+    fn parse(&self, input: &str) {
+        black_box(to_image(input, HEIGHT, WIDTH));
+    }
+
+    fn a(&self, input: &str, _steps: &StepCount) -> Answer {
+        solve_a(input)
+    }
+
+    fn b(&self, input: &str, _steps: &StepCount) -> Answer {
+        solve_b(input)
     }
-    println!();
 }
@@ -1,36 +1,68 @@
-const DAY_9: &str = include_str!("resources/9a.txt");
+pub const INPUT: &str = include_str!("resources/9a.txt");
 
+use std::hint::black_box;
+
+use crate::answer::Answer;
 use crate::intcode::{str_to_ints, RunResult, VM};
+use crate::solution::{Solution, StepCount};
 
-pub fn a() {
-    let code = str_to_ints(DAY_9);
+pub fn solve_a(input: &str, steps: &StepCount) -> Answer {
+    let code = str_to_ints(input);
 
     let mut vm = VM::new(&code);
     vm.give_input(1);
 
-    let run_result = vm.run();
+    let run_result = vm.run().unwrap();
     assert_eq!(run_result, RunResult::Stopped);
+    steps.add(vm.total_steps());
 
     let outputs = vm.get_all_outputs();
     assert_eq!(outputs.len(), 1);
 
-    println!("9a: {}", outputs[0]);
+    outputs[0].into()
 }
 
-pub fn b() {
-    let code = str_to_ints(DAY_9);
+pub fn solve_b(input: &str, steps: &StepCount) -> Answer {
+    let code = str_to_ints(input);
 
     let mut vm = VM::new(&code);
     vm.give_input(2);
 
-    let run_result = vm.run();
+    let run_result = vm.run().unwrap();
     assert_eq!(run_result, RunResult::Stopped);
+    steps.add(vm.total_steps());
 
     let outputs = vm.get_all_outputs();
     assert_eq!(outputs.len(), 1);
-    let out = outputs[0];
+    outputs[0].into()
+}
+
+pub struct Day09;
+
+impl Solution for Day09 {
+    fn day(&self) -> u32 {
+        9
+    }
+
+    fn name(&self) -> &'static str {
+        "Sensor Boost"
+    }
 
-    println!("9b: {}", out);
+    fn input(&self) -> &'static str {
+        INPUT
+    }
+
+    fn parse(&self, input: &str) {
+        black_box(str_to_ints(input));
+    }
+
+    fn a(&self, input: &str, steps: &StepCount) -> Answer {
+        solve_a(input, steps)
+    }
+
+    fn b(&self, input: &str, steps: &StepCount) -> Answer {
+        solve_b(input, steps)
+    }
 }
 
 #[cfg(test)]
@@ -39,12 +71,12 @@ mod tests {
 
     #[test]
     fn test_9a() {
-        let code = str_to_ints(DAY_9);
+        let code = str_to_ints(INPUT);
 
         let mut vm = VM::new(&code);
         vm.give_input(1);
 
-        let run_result = vm.run();
+        let run_result = vm.run().unwrap();
         assert_eq!(run_result, RunResult::Stopped);
 
         let outputs = vm.get_all_outputs();
@@ -55,12 +87,12 @@ mod tests {
 
     #[test]
     fn test_9b() {
-        let code = str_to_ints(DAY_9);
+        let code = str_to_ints(INPUT);
 
         let mut vm = VM::new(&code);
         vm.give_input(2);
 
-        let run_result = vm.run();
+        let run_result = vm.run().unwrap();
         assert_eq!(run_result, RunResult::Stopped);
 
         let outputs = vm.get_all_outputs();
@@ -76,7 +108,7 @@ mod tests {
         ];
 
         let mut vm = VM::new(&code);
-        vm.run();
+        vm.run().unwrap();
 
         let actual_output = vm.get_all_outputs();
 
@@ -92,7 +124,7 @@ mod tests {
         let code = [1102, 34915192, 34915192, 7, 4, 7, 99, 0];
 
         let mut vm = VM::new(&code);
-        vm.run();
+        vm.run().unwrap();
 
         let actual_output = vm.get_all_outputs();
 
@@ -109,7 +141,7 @@ mod tests {
         let code = [104, 1125899906842624, 99];
 
         let mut vm = VM::new(&code);
-        vm.run();
+        vm.run().unwrap();
 
         let actual_output = vm.get_all_outputs();
 
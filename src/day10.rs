@@ -1,7 +1,11 @@
 use std::cmp::{min, Ordering};
 use std::collections::BTreeMap;
+use std::hint::black_box;
 
-const DAY_10: &str = include_str!("resources/10a.txt");
+use crate::answer::Answer;
+use crate::solution::{Solution, StepCount};
+
+pub const INPUT: &str = include_str!("resources/10a.txt");
 
 struct AsteroidField {
     locs: Vec<(usize, usize)>,
@@ -268,16 +272,14 @@ fn get_best_loc(map: &str) -> BestLocResult {
     }
 }
 
-pub fn a() {
-    let out = get_best_loc(DAY_10).num_seen;
-
-    println!("10a: {}", out);
+pub fn solve_a(input: &str) -> Answer {
+    get_best_loc(input).num_seen.into()
 }
 
-pub fn b() {
-    let mut field = get_field(DAY_10);
+pub fn solve_b(input: &str) -> Answer {
+    let mut field = get_field(input);
 
-    let out = get_best_loc(DAY_10);
+    let out = get_best_loc(input);
     let (my_x, my_y) = (out.x, out.y);
 
     field.locs.retain(|(x, y)| *x != my_x || *y != my_y);
@@ -308,8 +310,35 @@ pub fn b() {
     }
 
     let (x, y) = deleted[199];
-    let ans = x * 100 + y;
-    println!("10b: {}", ans);
+    (x * 100 + y).into()
+}
+
+pub struct Day10;
+
+impl Solution for Day10 {
+    fn day(&self) -> u32 {
+        10
+    }
+
+    fn name(&self) -> &'static str {
+        "Monitoring Station"
+    }
+
+    fn input(&self) -> &'static str {
+        INPUT
+    }
+
+    fn parse(&self, input: &str) {
+        black_box(get_field(input));
+    }
+
+    fn a(&self, input: &str, _steps: &StepCount) -> Answer {
+        solve_a(input)
+    }
+
+    fn b(&self, input: &str, _steps: &StepCount) -> Answer {
+        solve_b(input)
+    }
 }
 
 #[cfg(test)]
@@ -318,7 +347,7 @@ mod tests {
 
     #[test]
     fn day_10_a() {
-        let actual = get_best_loc(DAY_10);
+        let actual = get_best_loc(INPUT);
         assert_eq!(actual.num_seen, 256);
     }
 
@@ -1,59 +1,30 @@
-use std::cmp::{max, min};
 use std::collections::HashMap;
+use std::hint::black_box;
 
+use crate::answer::Answer;
+use crate::grid::{Bounds, Direction, Pos};
 use crate::intcode::{str_to_ints, VM};
+use crate::solution::{Solution, StepCount};
 
-const DAY_11: &str = include_str!("resources/11a.txt");
+pub const INPUT: &str = include_str!("resources/11a.txt");
 
-#[derive(Copy, Clone, Eq, PartialEq, Debug)]
-enum Direction {
-    Up,
-    Down,
-    Left,
-    Right,
-}
-
-impl Direction {
-    fn to_delta(self) -> (i64, i64) {
-        use Direction::*;
-
-        match self {
-            Up => (0, -1),
-            Down => (0, 1),
-            Left => (-1, 0),
-            Right => (1, 0),
-        }
-    }
-
-    fn turn(self, change: i64) -> Direction {
-        use Direction::*;
-        match change {
-            0 => match self {
-                Up => Left,
-                Right => Up,
-                Down => Right,
-                Left => Down,
-            },
-            1 => match self {
-                Up => Right,
-                Left => Up,
-                Down => Left,
-                Right => Down,
-            },
-            _ => panic!("Unrecognized direction {}", change),
-        }
+// 0 turns left, 1 turns right
+fn turn(facing: Direction, change: i64) -> Direction {
+    match change {
+        0 => facing.turn_left(),
+        1 => facing.turn_right(),
+        _ => panic!("Unrecognized direction {}", change),
     }
 }
 
 struct RobotState {
-    x_pos: i64,
-    y_pos: i64,
+    pos: Pos,
     facing: Direction,
 }
 
 struct WorldState {
-    // (x, y) -> color; color is always 0 or 1 but lazy; 0 is black, 1 is white
-    colors: HashMap<(i64, i64), i64>,
+    // color is always 0 or 1 but lazy; 0 is black, 1 is white
+    colors: HashMap<Pos, i64>,
 }
 
 impl WorldState {
@@ -63,122 +34,112 @@ impl WorldState {
         }
     }
 
-    fn get_color(&self, x: i64, y: i64) -> i64 {
-        self.colors.get(&(x, y)).copied().unwrap_or(0)
+    fn get_color(&self, pos: Pos) -> i64 {
+        self.colors.get(&pos).copied().unwrap_or(0)
     }
 
-    fn set_color(&mut self, x: i64, y: i64, color: i64) {
+    fn set_color(&mut self, pos: Pos, color: i64) {
         assert!(
             color == 0 || color == 1,
             "Color should be valid; got {}",
             color
         );
-        self.colors.insert((x, y), color);
+        self.colors.insert(pos, color);
     }
 }
 
 impl RobotState {
-    fn new(x_pos: i64, y_pos: i64) -> Self {
+    fn new(pos: Pos) -> Self {
         RobotState {
-            x_pos,
-            y_pos,
+            pos,
             facing: Direction::Up,
         }
     }
 }
 
-pub fn a() {
-    let code = str_to_ints(DAY_11);
+pub fn solve_a(input: &str, steps: &StepCount) -> Answer {
+    let code = str_to_ints(input);
     let mut robot_vm = VM::new(&code);
 
     let mut world = WorldState::new();
-    let mut robot = RobotState::new(0, 0);
+    let mut robot = RobotState::new(Pos::ORIGIN);
 
     while !robot_vm.is_stopped() {
-        let color = world.get_color(robot.x_pos, robot.y_pos);
+        let color = world.get_color(robot.pos);
         robot_vm.give_input(color);
 
-        robot_vm.run();
+        robot_vm.run().unwrap();
 
         let new_color = robot_vm.get_next_output().unwrap();
-        let turn = robot_vm.get_next_output().unwrap();
+        let change = robot_vm.get_next_output().unwrap();
 
-        world.set_color(robot.x_pos, robot.y_pos, new_color);
+        world.set_color(robot.pos, new_color);
 
-        let new_direction = robot.facing.turn(turn);
-        robot.facing = new_direction;
-        let (x_delta, y_delta) = new_direction.to_delta();
-        robot.x_pos += x_delta;
-        robot.y_pos += y_delta;
+        robot.facing = turn(robot.facing, change);
+        robot.pos = robot.pos.step(robot.facing);
     }
+    steps.add(robot_vm.total_steps());
 
     let total_painted = world.colors.len();
 
-    println!("11a: {}", total_painted);
+    total_painted.into()
 }
 
-pub fn b() {
-    let code = str_to_ints(DAY_11);
+pub fn solve_b(input: &str, steps: &StepCount) -> Answer {
+    let code = str_to_ints(input);
     let mut robot_vm = VM::new(&code);
 
     let mut world = WorldState::new();
-    world.set_color(0, 0, 1);
+    world.set_color(Pos::ORIGIN, 1);
 
-    let mut robot = RobotState::new(0, 0);
+    let mut robot = RobotState::new(Pos::ORIGIN);
 
     while !robot_vm.is_stopped() {
-        let color = world.get_color(robot.x_pos, robot.y_pos);
+        let color = world.get_color(robot.pos);
         robot_vm.give_input(color);
 
-        robot_vm.run();
+        robot_vm.run().unwrap();
 
         let new_color = robot_vm.get_next_output().unwrap();
-        let turn = robot_vm.get_next_output().unwrap();
+        let change = robot_vm.get_next_output().unwrap();
 
-        world.set_color(robot.x_pos, robot.y_pos, new_color);
+        world.set_color(robot.pos, new_color);
 
-        let new_direction = robot.facing.turn(turn);
-        robot.facing = new_direction;
-        let (x_delta, y_delta) = new_direction.to_delta();
-        robot.x_pos += x_delta;
-        robot.y_pos += y_delta;
+        robot.facing = turn(robot.facing, change);
+        robot.pos = robot.pos.step(robot.facing);
     }
+    steps.add(robot_vm.total_steps());
 
-    let mut x_min = None;
-    let mut x_max = None;
-    let mut y_min = None;
-    let mut y_max = None;
-
-    for (x, y) in world.colors.keys() {
-        let (x, y) = (*x, *y);
+    let bounds = Bounds::of(world.colors.keys().copied()).unwrap();
+    let rows = bounds.bitmap(|pos| world.get_color(pos) != 0);
 
-        x_min = x_min.map(|old| min(old, x)).or(Some(x));
-        x_max = x_max.map(|old| max(old, x)).or(Some(x));
-        y_min = y_min.map(|old| min(old, y)).or(Some(y));
-        y_max = y_max.map(|old| max(old, y)).or(Some(y));
-    }
+    rows.into()
+}
 
-    let x_min = x_min.unwrap();
-    let x_max = x_max.unwrap();
-    let y_min = y_min.unwrap();
-    let y_max = y_max.unwrap();
+pub struct Day11;
 
-    println!("11b is a picture:");
+impl Solution for Day11 {
+    fn day(&self) -> u32 {
+        11
+    }
 
-    for y in y_min..=y_max {
-        let mut chars = vec![' ', ' '];
+    fn name(&self) -> &'static str {
+        "Space Police"
+    }
 
-        for x in x_min..=x_max {
-            let color = world.get_color(x, y);
-            let c = if color == 0 { ' ' } else { '#' };
-            chars.push(c);
-        }
-        let s = chars.into_iter().collect::<String>();
+    fn input(&self) -> &'static str {
+        INPUT
+    }
 
-        println!("{}", s);
+    fn parse(&self, input: &str) {
+        black_box(str_to_ints(input));
     }
 
-    // TODO: print the actual picture
+    fn a(&self, input: &str, steps: &StepCount) -> Answer {
+        solve_a(input, steps)
+    }
 
-    // println!("11b: {}", total_painted);
+    fn b(&self, input: &str, steps: &StepCount) -> Answer {
+        solve_b(input, steps)
+    }
 }
@@ -1,6 +1,11 @@
 use regex::Regex;
 
-const DAY_12: &str = include_str!("resources/12a.txt");
+use std::hint::black_box;
+
+use crate::answer::Answer;
+use crate::solution::{Solution, StepCount};
+
+pub const INPUT: &str = include_str!("resources/12a.txt");
 
 fn str_to_world_dims(state: &str) -> WorldStateDims {
     #[derive(Copy, Clone)]
@@ -146,10 +151,8 @@ fn do_total_energy(setup: &str, num_steps: usize) -> i64 {
     world.total_energy()
 }
 
-pub fn a() {
-    let total = do_total_energy(DAY_12, 1_000);
-
-    println!("12a: {}", total);
+pub fn solve_a(input: &str) -> Answer {
+    do_total_energy(input, 1_000).into()
 }
 
 #[derive(Copy, Clone, Eq, PartialEq, Debug)]
@@ -175,11 +178,11 @@ fn get_repeat_phase(mut dim: WorldStateDim) -> RepeatPhase {
     }
 }
 
-pub fn b() {
+pub fn solve_b(input: &str) -> Answer {
     // pretty embarassed to pull in a dependency for something this simple but i can't be arsed
     use num::integer::lcm;
 
-    let world = str_to_world_dims(DAY_12);
+    let world = str_to_world_dims(input);
 
     let x_phase = get_repeat_phase(world.x);
     let y_phase = get_repeat_phase(world.y);
@@ -195,7 +198,35 @@ pub fn b() {
 
     let period = lcm(lcm(x, y), z);
 
-    println!("12b: {}", period);
+    period.into()
+}
+
+pub struct Day12;
+
+impl Solution for Day12 {
+    fn day(&self) -> u32 {
+        12
+    }
+
+    fn name(&self) -> &'static str {
+        "The N-Body Problem"
+    }
+
+    fn input(&self) -> &'static str {
+        INPUT
+    }
+
+    fn parse(&self, input: &str) {
+        black_box(str_to_world_dims(input));
+    }
+
+    fn a(&self, input: &str, _steps: &StepCount) -> Answer {
+        solve_a(input)
+    }
+
+    fn b(&self, input: &str, _steps: &StepCount) -> Answer {
+        solve_b(input)
+    }
 }
 
 #[cfg(test)]
@@ -204,7 +235,7 @@ mod tests {
 
     #[test]
     fn test_12a() {
-        let actual = do_total_energy(DAY_12, 1_000);
+        let actual = do_total_energy(INPUT, 1_000);
         let expected = 14907;
 
         assert_eq!(actual, expected);
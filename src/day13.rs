@@ -1,17 +1,22 @@
-const DAY_13: &str = include_str!("resources/13a.txt");
+pub const INPUT: &str = include_str!("resources/13a.txt");
 
 use std::collections::HashMap;
+use std::hint::black_box;
 
+use crate::answer::Answer;
+use crate::intcode::patch::Patch;
 use crate::intcode::{str_to_ints, RunResult, VM};
+use crate::solution::{Solution, StepCount};
 
-pub fn a() {
-    let code = str_to_ints(DAY_13);
+pub fn solve_a(input: &str, steps: &StepCount) -> Answer {
+    let code = str_to_ints(input);
     let mut board: HashMap<(i64, i64), i64> = HashMap::new();
 
     let mut vm = VM::new(&code);
 
-    let run_result = vm.run();
+    let run_result = vm.run().unwrap();
     assert_eq!(run_result, RunResult::Stopped);
+    steps.add(vm.total_steps());
 
     while let Some(x) = vm.get_next_output() {
         let y = vm.get_next_output().expect("Y should exist");
@@ -22,19 +27,20 @@ pub fn a() {
 
     let num_blocks = board.values().filter(|tile| **tile == 2).count();
 
-    println!("13a: {}", num_blocks);
+    num_blocks.into()
 }
 
-pub fn b() {
-    let mut code = str_to_ints(DAY_13);
-    code[0] = 2;
+pub fn solve_b(input: &str, steps: &StepCount) -> Answer {
+    let code = str_to_ints(input);
+    // two quarters, for free play
+    let coins = Patch::new().with(0, 2);
 
     let mut board: HashMap<(i64, i64), i64> = HashMap::new();
     let mut score = None;
 
-    let mut vm = VM::new(&code);
+    let mut vm = VM::new(&code).with_patch(&coins);
 
-    while vm.run() == RunResult::NeedInput {
+    while vm.run().unwrap() == RunResult::NeedInput {
         while let Some(x) = vm.get_next_output() {
             let y = vm.get_next_output().expect("Y should exist");
             let tile = vm.get_next_output().expect("Tile should exist");
@@ -71,6 +77,7 @@ pub fn b() {
         };
         vm.give_input(change);
     }
+    steps.add(vm.total_steps());
 
     while let Some(x) = vm.get_next_output() {
         let y = vm.get_next_output().expect("Y should exist");
@@ -83,5 +90,33 @@ pub fn b() {
         }
     }
 
-    println!("13b: {}", score.unwrap());
+    score.unwrap().into()
+}
+
+pub struct Day13;
+
+impl Solution for Day13 {
+    fn day(&self) -> u32 {
+        13
+    }
+
+    fn name(&self) -> &'static str {
+        "Care Package"
+    }
+
+    fn input(&self) -> &'static str {
+        INPUT
+    }
+
+    fn parse(&self, input: &str) {
+        black_box(str_to_ints(input));
+    }
+
+    fn a(&self, input: &str, steps: &StepCount) -> Answer {
+        solve_a(input, steps)
+    }
+
+    fn b(&self, input: &str, steps: &StepCount) -> Answer {
+        solve_b(input, steps)
+    }
 }
@@ -1,7 +1,11 @@
 use std::collections::HashMap;
+use std::hint::black_box;
 use std::iter::Peekable;
 
-const DAY_14: &str = include_str!("resources/14a.txt");
+use crate::answer::Answer;
+use crate::solution::{Solution, StepCount};
+
+pub const INPUT: &str = include_str!("resources/14a.txt");
 
 /// Made into a module so I can collapse it, it's not that interesting
 mod parse {
@@ -225,17 +229,41 @@ fn fuel_for_ore(data: &str, goal_elt: &str, ore_cap: i64) -> i64 {
     fuel_min
 }
 
-pub fn a() {
-    let reactions = str_to_reactions(DAY_14);
-    let out = ore_for_fuel(&reactions, "FUEL", 1);
+pub fn solve_a(input: &str) -> Answer {
+    let reactions = str_to_reactions(input);
+    ore_for_fuel(&reactions, "FUEL", 1).into()
+}
 
-    println!("14a: {}", out);
+pub fn solve_b(input: &str) -> Answer {
+    fuel_for_ore(input, "FUEL", 1000000000000).into()
 }
 
-pub fn b() {
-    let fuel_cap = fuel_for_ore(DAY_14, "FUEL", 1000000000000);
+pub struct Day14;
+
+impl Solution for Day14 {
+    fn day(&self) -> u32 {
+        14
+    }
 
-    println!("14b: {}", fuel_cap);
+    fn name(&self) -> &'static str {
+        "Space Stoichiometry"
+    }
+
+    fn input(&self) -> &'static str {
+        INPUT
+    }
+
+    fn parse(&self, input: &str) {
+        black_box(str_to_reactions(input));
+    }
+
+    fn a(&self, input: &str, _steps: &StepCount) -> Answer {
+        solve_a(input)
+    }
+
+    fn b(&self, input: &str, _steps: &StepCount) -> Answer {
+        solve_b(input)
+    }
 }
 
 #[cfg(test)]
@@ -1,67 +1,28 @@
-const DAY_15: &str = include_str!("resources/15a.txt");
+pub const INPUT: &str = include_str!("resources/15a.txt");
 
-use std::cmp::{max, min};
 use std::collections::{HashMap, HashSet, VecDeque};
+use std::hint::black_box;
 
+use crate::answer::Answer;
+use crate::grid::{Bounds, Direction, Pos};
+use crate::intcode::memory::Memory;
 use crate::intcode::{str_to_ints, RunResult, VM};
+use crate::solution::{Solution, StepCount};
 
-#[derive(Copy, Clone, Eq, PartialEq, Debug, Hash)]
-struct Pos {
-    x: i64,
-    y: i64,
-}
-
-impl Pos {
-    fn with(self, vel: Vel) -> Pos {
-        Pos {
-            x: self.x + vel.x,
-            y: self.y + vel.y,
-        }
-    }
-
-    fn with_dir(self, dir: Direction) -> Pos {
-        let vel = dir.to_vel();
-        self.with(vel)
-    }
-
-    fn neighbors(self) -> [Pos; 4] {
-        use Direction::*;
-
-        [
-            self.with_dir(Up),
-            self.with_dir(Down),
-            self.with_dir(Left),
-            self.with_dir(Right),
-        ]
-    }
-}
-
-#[derive(Copy, Clone, Eq, PartialEq, Debug, Hash)]
-struct Vel {
-    x: i64,
-    y: i64,
-}
-
-#[derive(Clone, Debug)]
-struct World {
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub(crate) struct World {
     known: HashMap<Pos, TileState>,
-    x_min: i64,
-    x_max: i64,
-    y_min: i64,
-    y_max: i64,
+    bounds: Bounds,
 }
 
 impl World {
     fn new() -> Self {
         let mut known = HashMap::new();
-        known.insert(Pos { x: 0, y: 0 }, TileState::Open);
+        known.insert(Pos::ORIGIN, TileState::Open);
 
         World {
             known,
-            x_min: 0,
-            x_max: 0,
-            y_min: 0,
-            y_max: 0,
+            bounds: Bounds::new(Pos::ORIGIN),
         }
     }
 
@@ -126,8 +87,8 @@ impl World {
         // Pre: robot_pos is in self.bounds
         let mut out = String::new();
 
-        for y in self.y_min..=self.y_max {
-            for x in self.x_min..=self.x_max {
+        for y in self.bounds.min.y..=self.bounds.max.y {
+            for x in self.bounds.min.x..=self.bounds.max.x {
                 let pos = Pos { x, y };
                 let to_push = {
                     if robot_pos == pos {
@@ -159,10 +120,7 @@ impl World {
         if let Some(old_state) = old {
             assert_eq!(old_state, state);
         } else {
-            self.x_min = min(self.x_min, pos.x);
-            self.x_max = max(self.x_max, pos.x);
-            self.y_min = min(self.y_min, pos.y);
-            self.y_max = max(self.y_max, pos.y);
+            self.bounds.include(pos);
         }
     }
 }
@@ -174,46 +132,15 @@ enum TileState {
     Oxygen, // target
 }
 
-#[derive(Copy, Clone, Eq, PartialEq, Debug, Hash)]
-enum Direction {
-    Left,
-    Right,
-    Up,
-    Down,
-}
-
-impl Direction {
-    fn to_vel(self) -> Vel {
-        use Direction::*;
-
-        match self {
-            Left => Vel { x: -1, y: 0 },
-            Right => Vel { x: 1, y: 0 },
-            Up => Vel { x: 0, y: -1 },
-            Down => Vel { x: 0, y: 1 },
-        }
-    }
-
-    fn to_command(self) -> i64 {
-        use Direction::*;
-
-        match self {
-            Left => 3,
-            Right => 4,
-            Up => 1,
-            Down => 2,
-        }
-    }
-
-    fn invert(self) -> Direction {
-        use Direction::*;
+// What to send the robot to move that way
+fn command(dir: Direction) -> i64 {
+    use Direction::*;
 
-        match self {
-            Left => Right,
-            Right => Left,
-            Up => Down,
-            Down => Up,
-        }
+    match dir {
+        Left => 3,
+        Right => 4,
+        Up => 1,
+        Down => 2,
     }
 }
 
@@ -237,19 +164,30 @@ impl RobotResponse {
     }
 }
 
-fn build_map(code: &[i64]) -> World {
+fn build_map(code: &[i64], steps: &StepCount) -> World {
+    explore(VM::new(code), steps)
+}
+
+// Runs until the robot wants its next move. The VMs here are clones of each other, so
+// each run is counted, rather than their totals, which would count what they share twice.
+fn run_robot<M: Memory>(vm: &mut VM<M>, steps: &StepCount) {
+    assert_eq!(vm.run(), Ok(RunResult::NeedInput));
+    steps.add(vm.last_run_steps());
+}
+
+// Generic so the memory backends can be compared; see benches.rs
+pub(crate) fn explore<M: Memory>(mut vm: VM<M>, steps: &StepCount) -> World {
     use Direction::*;
     use RobotResponse::*;
 
     let mut world = World::new();
 
-    let mut vm = VM::new(code);
-    assert_eq!(vm.run(), RunResult::NeedInput);
+    run_robot(&mut vm, steps);
     assert_eq!(vm.get_next_output(), None);
 
     // but now ... ugh?
-    let mut search_from: HashMap<Pos, VM> = HashMap::new();
-    search_from.insert(Pos { x: 0, y: 0 }, vm);
+    let mut search_from: HashMap<Pos, VM<M>> = HashMap::new();
+    search_from.insert(Pos::ORIGIN, vm);
 
     // Every time we make a move, if we discover a new place, we drop a pin there
     // which is a clone of the VM we used to get there. This is a lot of clones, but
@@ -263,28 +201,28 @@ fn build_map(code: &[i64]) -> World {
         let mut vm = search_from.remove(&pos).unwrap();
 
         for &dir in &[Up, Left, Right, Down] {
-            let next_pos = pos.with_dir(dir);
+            let next_pos = pos.step(dir);
             if world.get_state(next_pos).is_none() {
-                vm.give_input(dir.to_command());
-                assert_eq!(vm.run(), RunResult::NeedInput);
+                vm.give_input(command(dir));
+                run_robot(&mut vm, steps);
 
                 match RobotResponse::from_output(vm.get_next_output().unwrap()) {
                     Moved => {
                         world.set_state(next_pos, TileState::Open);
                         search_from.entry(next_pos).or_insert_with(|| vm.clone());
-                        vm.give_input(dir.invert().to_command());
+                        vm.give_input(command(dir.opposite()));
                     }
                     MovedAndFoundOxygen => {
                         world.set_state(next_pos, TileState::Oxygen);
                         search_from.entry(next_pos).or_insert_with(|| vm.clone());
-                        vm.give_input(dir.invert().to_command());
+                        vm.give_input(command(dir.opposite()));
                     }
                     HitWall => {
                         world.set_state(next_pos, TileState::Wall);
                     }
                 }
 
-                assert_eq!(vm.run(), RunResult::NeedInput);
+                run_robot(&mut vm, steps);
                 let _ = vm.get_next_output(); // ignored, because it's backtracking
             }
         }
@@ -293,11 +231,11 @@ fn build_map(code: &[i64]) -> World {
     world
 }
 
-pub fn a() {
-    let code = &str_to_ints(DAY_15);
-    let map = build_map(&code);
+pub fn solve_a(input: &str, steps: &StepCount) -> Answer {
+    let code = &str_to_ints(input);
+    let map = build_map(code, steps);
 
-    let start_pos = Pos { x: 0, y: 0 };
+    let start_pos = Pos::ORIGIN;
     let oxygen_pos: Pos = map
         .known
         .iter()
@@ -308,12 +246,12 @@ pub fn a() {
 
     let shortest_path_len = map.shortest_path_len(start_pos, oxygen_pos);
 
-    println!("15a: {}", shortest_path_len);
+    shortest_path_len.into()
 }
 
-pub fn b() {
-    let code = &str_to_ints(DAY_15);
-    let map = build_map(&code);
+pub fn solve_b(input: &str, steps: &StepCount) -> Answer {
+    let code = &str_to_ints(input);
+    let map = build_map(code, steps);
 
     let oxygen_pos: Pos = map
         .known
@@ -325,5 +263,33 @@ pub fn b() {
 
     let spread_time = map.spread_time(oxygen_pos);
 
-    println!("15b: {}", spread_time);
+    spread_time.into()
+}
+
+pub struct Day15;
+
+impl Solution for Day15 {
+    fn day(&self) -> u32 {
+        15
+    }
+
+    fn name(&self) -> &'static str {
+        "Oxygen System"
+    }
+
+    fn input(&self) -> &'static str {
+        INPUT
+    }
+
+    fn parse(&self, input: &str) {
+        black_box(str_to_ints(input));
+    }
+
+    fn a(&self, input: &str, steps: &StepCount) -> Answer {
+        solve_a(input, steps)
+    }
+
+    fn b(&self, input: &str, steps: &StepCount) -> Answer {
+        solve_b(input, steps)
+    }
 }
@@ -1,4 +1,9 @@
-const DAY_16: &str = include_str!("resources/16a.txt");
+use std::hint::black_box;
+
+use crate::answer::Answer;
+use crate::solution::{Part, Solution, StepCount};
+
+pub const INPUT: &str = include_str!("resources/16a.txt");
 
 fn str_to_ints(data: &str) -> Vec<i64> {
     data.trim()
@@ -53,21 +58,52 @@ fn lots_of_fft(data: &[i64]) -> i64 {
     total
 }
 
-pub fn a() {
-    let input_data = str_to_ints(DAY_16);
-
-    let total = lots_of_fft(&input_data);
+pub fn solve_a(input: &str) -> Answer {
+    let input_data = str_to_ints(input);
 
-    println!("16a: {:?}", total);
+    lots_of_fft(&input_data).into()
 }
 
-pub fn b() {
+pub fn solve_b(_input: &str) -> Answer {
     // I heard you like project euler
     // Do some math you lazy twit
 
     unimplemented!()
 }
 
+pub struct Day16;
+
+impl Solution for Day16 {
+    fn day(&self) -> u32 {
+        16
+    }
+
+    fn name(&self) -> &'static str {
+        "Flawed Frequency Transmission"
+    }
+
+    fn input(&self) -> &'static str {
+        INPUT
+    }
+
+    fn parse(&self, input: &str) {
+        black_box(str_to_ints(input));
+    }
+
+    fn a(&self, input: &str, _steps: &StepCount) -> Answer {
+        solve_a(input)
+    }
+
+    fn b(&self, input: &str, _steps: &StepCount) -> Answer {
+        solve_b(input)
+    }
+
+    // b isn't done yet
+    fn parts(&self) -> &'static [Part] {
+        &[Part::A]
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
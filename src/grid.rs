@@ -0,0 +1,168 @@
+use std::cmp::{max, min};
+
+// A point on a grid, with y increasing downwards as on screen
+#[derive(Copy, Clone, Eq, PartialEq, Debug, Hash, Default)]
+pub struct Pos {
+    pub x: i64,
+    pub y: i64,
+}
+
+impl Pos {
+    pub const ORIGIN: Pos = Pos { x: 0, y: 0 };
+
+    pub fn new(x: i64, y: i64) -> Self {
+        Pos { x, y }
+    }
+
+    pub fn step(self, dir: Direction) -> Pos {
+        let (dx, dy) = dir.delta();
+        Pos {
+            x: self.x + dx,
+            y: self.y + dy,
+        }
+    }
+
+    pub fn neighbors(self) -> [Pos; 4] {
+        Direction::ALL.map(|dir| self.step(dir))
+    }
+
+    pub fn manhattan(self, other: Pos) -> i64 {
+        (self.x - other.x).abs() + (self.y - other.y).abs()
+    }
+}
+
+#[derive(Copy, Clone, Eq, PartialEq, Debug, Hash)]
+pub enum Direction {
+    Up,
+    Down,
+    Left,
+    Right,
+}
+
+impl Direction {
+    pub const ALL: [Direction; 4] = [
+        Direction::Up,
+        Direction::Down,
+        Direction::Left,
+        Direction::Right,
+    ];
+
+    // As (dx, dy)
+    pub fn delta(self) -> (i64, i64) {
+        use Direction::*;
+
+        match self {
+            Up => (0, -1),
+            Down => (0, 1),
+            Left => (-1, 0),
+            Right => (1, 0),
+        }
+    }
+
+    pub fn turn_left(self) -> Direction {
+        use Direction::*;
+
+        match self {
+            Up => Left,
+            Left => Down,
+            Down => Right,
+            Right => Up,
+        }
+    }
+
+    pub fn turn_right(self) -> Direction {
+        self.turn_left().opposite()
+    }
+
+    pub fn opposite(self) -> Direction {
+        use Direction::*;
+
+        match self {
+            Up => Down,
+            Down => Up,
+            Left => Right,
+            Right => Left,
+        }
+    }
+}
+
+// The smallest rectangle holding some points, corners included
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+pub struct Bounds {
+    pub min: Pos,
+    pub max: Pos,
+}
+
+impl Bounds {
+    pub fn new(pos: Pos) -> Self {
+        Bounds { min: pos, max: pos }
+    }
+
+    // None if there aren't any points
+    pub fn of<I: IntoIterator<Item = Pos>>(points: I) -> Option<Self> {
+        let mut points = points.into_iter();
+        let mut bounds = Bounds::new(points.next()?);
+        for pos in points {
+            bounds.include(pos);
+        }
+        Some(bounds)
+    }
+
+    // Grows to take in the point, if it's outside
+    pub fn include(&mut self, pos: Pos) {
+        self.min = Pos::new(min(self.min.x, pos.x), min(self.min.y, pos.y));
+        self.max = Pos::new(max(self.max.x, pos.x), max(self.max.y, pos.y));
+    }
+
+    pub fn contains(&self, pos: Pos) -> bool {
+        (self.min.x..=self.max.x).contains(&pos.x) && (self.min.y..=self.max.y).contains(&pos.y)
+    }
+
+    // A row per y, top first, with lit telling which pixels are on
+    pub fn bitmap<F: Fn(Pos) -> bool>(&self, lit: F) -> Vec<Vec<bool>> {
+        (self.min.y..=self.max.y)
+            .map(|y| {
+                (self.min.x..=self.max.x)
+                    .map(|x| lit(Pos::new(x, y)))
+                    .collect()
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn turning() {
+        let mut dir = Direction::Up;
+        let mut pos = Pos::ORIGIN;
+        for _ in 0..4 {
+            dir = dir.turn_right();
+            pos = pos.step(dir);
+        }
+        assert_eq!((dir, pos), (Direction::Up, Pos::ORIGIN));
+
+        assert_eq!(Direction::Left.turn_left(), Direction::Down);
+        assert_eq!(Direction::Left.turn_right(), Direction::Up);
+        assert_eq!(Pos::new(3, -4).manhattan(Pos::ORIGIN), 7);
+    }
+
+    #[test]
+    fn bounds() {
+        assert_eq!(Bounds::of(vec![]), None);
+
+        let points = [Pos::new(1, 0), Pos::new(-1, 1)];
+        let bounds = Bounds::of(points.iter().copied()).unwrap();
+        assert_eq!(bounds.min, Pos::new(-1, 0));
+        assert_eq!(bounds.max, Pos::new(1, 1));
+        assert!(bounds.contains(Pos::new(0, 1)));
+        assert!(!bounds.contains(Pos::new(0, 2)));
+
+        assert_eq!(
+            bounds.bitmap(|pos| points.contains(&pos)),
+            vec![vec![false, false, true], vec![true, false, false]]
+        );
+    }
+}
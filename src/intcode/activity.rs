@@ -0,0 +1,82 @@
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::time::Duration;
+
+// Shared by the threads of a graph's or network's run_threaded, for telling when nothing
+// more can happen: every node has finished, or is waiting with nothing queued for it.
+// Nodes are numbered from 0, and queued counts what's been sent to a node but not read.
+pub(super) struct Activity {
+    waiting: Vec<AtomicBool>,
+    queued: Vec<AtomicUsize>,
+    finished: Vec<AtomicBool>,
+    // bumped on every send and read, so a check can tell whether anything moved under it
+    events: AtomicUsize,
+}
+
+// How often run_threaded checks, while nothing else happens
+pub(super) const QUIET_CHECK: Duration = Duration::from_millis(1);
+
+impl Activity {
+    pub fn new(size: usize) -> Self {
+        Activity {
+            waiting: (0..size).map(|_| AtomicBool::new(false)).collect(),
+            queued: (0..size).map(|_| AtomicUsize::new(0)).collect(),
+            finished: (0..size).map(|_| AtomicBool::new(false)).collect(),
+            events: AtomicUsize::new(0),
+        }
+    }
+
+    pub fn sent(&self, node: usize) {
+        self.queued[node].fetch_add(1, Ordering::SeqCst);
+        self.events.fetch_add(1, Ordering::SeqCst);
+    }
+
+    pub fn received(&self, node: usize) {
+        self.queued[node].fetch_sub(1, Ordering::SeqCst);
+        self.events.fetch_add(1, Ordering::SeqCst);
+    }
+
+    pub fn queued(&self, node: usize) -> usize {
+        self.queued[node].load(Ordering::SeqCst)
+    }
+
+    pub fn set_waiting(&self, node: usize, waiting: bool) {
+        self.waiting[node].store(waiting, Ordering::SeqCst);
+    }
+
+    pub fn finish(&self, node: usize) {
+        self.finished[node].store(true, Ordering::SeqCst);
+    }
+
+    pub fn quiet(&self) -> bool {
+        let before = self.events.load(Ordering::SeqCst);
+        let stuck = (0..self.waiting.len()).all(|i| {
+            self.finished[i].load(Ordering::SeqCst)
+                || (self.waiting[i].load(Ordering::SeqCst) && self.queued(i) == 0)
+        });
+        stuck && self.events.load(Ordering::SeqCst) == before
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn quiet() {
+        let activity = Activity::new(2);
+        assert!(!activity.quiet());
+
+        activity.finish(0);
+        activity.set_waiting(1, true);
+        assert!(activity.quiet());
+
+        // waiting, but something's on its way
+        activity.sent(1);
+        assert!(!activity.quiet());
+        activity.received(1);
+        assert!(activity.quiet());
+
+        activity.set_waiting(1, false);
+        assert!(!activity.quiet());
+    }
+}
@@ -0,0 +1,164 @@
+use std::io::{self, BufRead, Write};
+
+use super::memory::{DenseMemory, Memory};
+use super::{RunResult, VmError, VM};
+
+// What a program printed between two pauses
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct AsciiOutput {
+    pub text: String,
+    // Outputs too big to be ASCII; programs use these to report their real answer
+    pub answers: Vec<i64>,
+    pub result: RunResult,
+}
+
+// Wraps a VM for programs which talk in lines of ASCII text
+pub struct AsciiVm<M: Memory = DenseMemory> {
+    vm: VM<M>,
+}
+
+impl AsciiVm {
+    pub fn new(code: &[i64]) -> Self {
+        AsciiVm::from_vm(VM::new(code))
+    }
+}
+
+impl<M: Memory> AsciiVm<M> {
+    pub fn from_vm(vm: VM<M>) -> Self {
+        AsciiVm { vm }
+    }
+
+    // Queues the line, and the newline the program expects after it
+    pub fn send_line(&mut self, line: &str) {
+        for c in line.chars().chain(Some('\n')) {
+            self.vm.give_input(c as i64);
+        }
+    }
+
+    // On a fault, anything printed is left queued in the VM
+    pub fn run(&mut self) -> Result<AsciiOutput, VmError> {
+        let result = self.vm.run()?;
+
+        let mut text = String::new();
+        let mut answers = Vec::new();
+        for val in self.vm.get_all_outputs() {
+            if (0..128).contains(&val) {
+                text.push(val as u8 as char);
+            } else {
+                answers.push(val);
+            }
+        }
+
+        Ok(AsciiOutput {
+            text,
+            answers,
+            result,
+        })
+    }
+
+    // Shows the program's text, and sends it each line typed, until it stops or the
+    // input runs out
+    pub fn interact<R: BufRead, W: Write>(&mut self, input: R, mut out: W) -> io::Result<()> {
+        let mut lines = input.lines();
+
+        loop {
+            let output = match self.run() {
+                Ok(output) => output,
+                Err(e) => {
+                    writeln!(out, "Program faulted: {}", e)?;
+                    return Ok(());
+                }
+            };
+
+            write!(out, "{}", output.text)?;
+            for answer in output.answers {
+                writeln!(out, "Answer: {}", answer)?;
+            }
+            out.flush()?;
+
+            match output.result {
+                RunResult::NeedInput => match lines.next() {
+                    Some(line) => self.send_line(&line?),
+                    None => return Ok(()),
+                },
+                RunResult::Stopped => return Ok(()),
+                other => {
+                    writeln!(out, "Program paused: {:?}", other)?;
+                    return Ok(());
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::intcode::asm::assemble;
+
+    // Prints a prompt, then echoes lines back until it gets an empty one, then prints
+    // how many characters it echoed
+    fn echo() -> Vec<i64> {
+        assemble(
+            "
+                   OUT #62
+                   OUT #32
+            line:  IN [c]
+                   EQ [c], #10, [t]
+                   JT [t], #end
+                   OUT [c]
+                   ADD [n], #1, [n]
+                   JT #1, #line
+            end:   JF [n], #done
+                   OUT #10
+                   ADD [total], [n], [total]
+                   ADD #0, #0, [n]
+                   JT #1, #line
+            done:  ADD [total], #1000, [total]
+                   OUT [total]
+                   HLT
+            c:     DATA 0
+            t:     DATA 0
+            n:     DATA 0
+            total: DATA 0
+            ",
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn lines_and_answers() {
+        let mut vm = AsciiVm::new(&echo());
+
+        let output = vm.run().unwrap();
+        assert_eq!(output.text, "> ");
+        assert_eq!(output.result, RunResult::NeedInput);
+
+        vm.send_line("hello");
+        vm.send_line("there");
+        let output = vm.run().unwrap();
+        assert_eq!(output.text, "hello\nthere\n");
+        assert!(output.answers.is_empty());
+
+        vm.send_line("");
+        let output = vm.run().unwrap();
+        assert_eq!(output.text, "");
+        assert_eq!(output.answers, vec![1010]);
+        assert_eq!(output.result, RunResult::Stopped);
+    }
+
+    #[test]
+    fn interactive() {
+        let mut vm = AsciiVm::new(&echo());
+        let mut out = Vec::new();
+        vm.interact("abc\n\n".as_bytes(), &mut out).unwrap();
+        assert_eq!(String::from_utf8(out).unwrap(), "> abc\nAnswer: 1003\n");
+
+        // running out of input just ends the session
+        let mut vm = AsciiVm::new(&echo());
+        let mut out = Vec::new();
+        vm.interact("abc\n".as_bytes(), &mut out).unwrap();
+        assert_eq!(String::from_utf8(out).unwrap(), "> abc\n");
+        assert_eq!(vm.run().unwrap().result, RunResult::NeedInput);
+    }
+}
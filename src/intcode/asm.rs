@@ -0,0 +1,561 @@
+use std::collections::HashMap;
+use std::convert::TryFrom;
+use std::fmt;
+use std::iter::Peekable;
+use std::str::Chars;
+
+// Assembles the syntax printed by the disassembler back into a program. One instruction per line:
+//
+//         ; comments run to the end of the line
+//         size = 3               ; constants
+//   loop: ADD [ptr], #size*2, rb-1
+//         JT #1, #loop
+//   ptr:  DATA 10, loop+1        ; raw words (DB also works)
+//
+// Operands are [addr] (position), #val (immediate) or rb+off (relative); every value can be
+// an expression over numbers, labels and constants with + - * and parentheses. A leading
+// address like "   12:" (as in a listing) is checked against the actual address.
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct AsmError {
+    // 1-indexed, like an editor
+    pub line: usize,
+    pub message: String,
+}
+
+impl fmt::Display for AsmError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "line {}: {}", self.line, self.message)
+    }
+}
+
+impl std::error::Error for AsmError {}
+
+// mnemonic, op code, number of parameters
+const OPS: [(&str, i64, usize); 10] = [
+    ("ADD", 1, 3),
+    ("MUL", 2, 3),
+    ("IN", 3, 1),
+    ("OUT", 4, 1),
+    ("JT", 5, 2),
+    ("JF", 6, 2),
+    ("LT", 7, 3),
+    ("EQ", 8, 3),
+    ("ARB", 9, 1),
+    ("HLT", 99, 0),
+];
+
+#[derive(Clone, Debug, PartialEq)]
+enum Token {
+    // without its sign, which is only known once it's parsed
+    Num(u64),
+    Ident(String),
+    Punct(char),
+}
+
+#[derive(Clone, Debug)]
+enum Expr {
+    Num(i64),
+    Name(String),
+    Neg(Box<Expr>),
+    Add(Box<Expr>, Box<Expr>),
+    Sub(Box<Expr>, Box<Expr>),
+    Mul(Box<Expr>, Box<Expr>),
+}
+
+#[derive(Clone, Debug)]
+enum Operand {
+    Position(Expr),
+    Immediate(Expr),
+    Relative(Expr),
+}
+
+#[derive(Clone, Debug)]
+enum Stmt {
+    Instruction(i64, Vec<Operand>),
+    Data(Vec<Expr>),
+    Constant(String, Expr),
+}
+
+fn tokenize(text: &str) -> Result<Vec<Token>, String> {
+    let mut out = Vec::new();
+    let mut chars: Peekable<Chars> = text.chars().peekable();
+
+    while let Some(&c) = chars.peek() {
+        if c == ';' {
+            break;
+        } else if c.is_whitespace() {
+            chars.next();
+        } else if c.is_ascii_digit() {
+            let mut s = String::new();
+            while let Some(&d) = chars.peek() {
+                if !d.is_ascii_digit() {
+                    break;
+                }
+                s.push(d);
+                chars.next();
+            }
+            let val = s
+                .parse::<u64>()
+                .map_err(|_| format!("Number {} is too large", s))?;
+            out.push(Token::Num(val));
+        } else if c.is_ascii_alphabetic() || c == '_' || c == '.' {
+            let mut s = String::new();
+            while let Some(&d) = chars.peek() {
+                if !(d.is_ascii_alphanumeric() || d == '_' || d == '.') {
+                    break;
+                }
+                s.push(d);
+                chars.next();
+            }
+            out.push(Token::Ident(s));
+        } else if "[]#+-*(),:=".contains(c) {
+            out.push(Token::Punct(c));
+            chars.next();
+        } else {
+            return Err(format!("Unexpected character '{}'", c));
+        }
+    }
+
+    Ok(out)
+}
+
+struct Parser {
+    tokens: Vec<Token>,
+    pos: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.pos)
+    }
+
+    fn peek_at(&self, offset: usize) -> Option<&Token> {
+        self.tokens.get(self.pos + offset)
+    }
+
+    fn next(&mut self) -> Option<Token> {
+        let out = self.tokens.get(self.pos).cloned();
+        self.pos += 1;
+        out
+    }
+
+    fn at_end(&self) -> bool {
+        self.pos >= self.tokens.len()
+    }
+
+    fn eat(&mut self, c: char) -> bool {
+        if self.peek() == Some(&Token::Punct(c)) {
+            self.pos += 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    fn expect(&mut self, c: char) -> Result<(), String> {
+        if self.eat(c) {
+            Ok(())
+        } else {
+            Err(format!("Expected '{}'", c))
+        }
+    }
+
+    fn expr(&mut self) -> Result<Expr, String> {
+        let mut out = self.term()?;
+        loop {
+            if self.eat('+') {
+                out = Expr::Add(Box::new(out), Box::new(self.term()?));
+            } else if self.eat('-') {
+                out = Expr::Sub(Box::new(out), Box::new(self.term()?));
+            } else {
+                return Ok(out);
+            }
+        }
+    }
+
+    fn term(&mut self) -> Result<Expr, String> {
+        let mut out = self.factor()?;
+        while self.eat('*') {
+            out = Expr::Mul(Box::new(out), Box::new(self.factor()?));
+        }
+        Ok(out)
+    }
+
+    fn factor(&mut self) -> Result<Expr, String> {
+        match self.next() {
+            Some(Token::Num(n)) => literal(n, false),
+            Some(Token::Ident(name)) => Ok(Expr::Name(name)),
+            Some(Token::Punct('-')) => match self.peek() {
+                // part of the number, since the lowest i64 is too large to negate
+                Some(&Token::Num(n)) => {
+                    self.pos += 1;
+                    literal(n, true)
+                }
+                _ => Ok(Expr::Neg(Box::new(self.factor()?))),
+            },
+            Some(Token::Punct('+')) => self.factor(),
+            Some(Token::Punct('(')) => {
+                let out = self.expr()?;
+                self.expect(')')?;
+                Ok(out)
+            }
+            Some(other) => Err(format!("Expected a value, got {:?}", other)),
+            None => Err("Expected a value, got end of line".to_string()),
+        }
+    }
+
+    fn operand(&mut self) -> Result<Operand, String> {
+        if self.eat('[') {
+            let out = self.expr()?;
+            self.expect(']')?;
+            return Ok(Operand::Position(out));
+        }
+
+        if self.eat('#') {
+            return Ok(Operand::Immediate(self.expr()?));
+        }
+
+        if let Some(Token::Ident(name)) = self.peek() {
+            if name.eq_ignore_ascii_case("rb") {
+                self.pos += 1;
+                // the sign of the offset is parsed as part of the expression
+                return match self.peek() {
+                    None | Some(Token::Punct(',')) => Ok(Operand::Relative(Expr::Num(0))),
+                    _ => Ok(Operand::Relative(self.expr()?)),
+                };
+            }
+        }
+
+        Err("Expected an operand like [addr], #val or rb+off".to_string())
+    }
+
+    fn comma_separated<T>(
+        &mut self,
+        mut f: impl FnMut(&mut Parser) -> Result<T, String>,
+    ) -> Result<Vec<T>, String> {
+        let mut out = Vec::new();
+        if self.at_end() {
+            return Ok(out);
+        }
+
+        out.push(f(self)?);
+        while self.eat(',') {
+            out.push(f(self)?);
+        }
+
+        Ok(out)
+    }
+}
+
+fn literal(magnitude: u64, negative: bool) -> Result<Expr, String> {
+    let val = if negative {
+        -i128::from(magnitude)
+    } else {
+        i128::from(magnitude)
+    };
+    i64::try_from(val)
+        .map(Expr::Num)
+        .map_err(|_| format!("Number {} is too large", val))
+}
+
+struct ParsedLine {
+    address: Option<u64>,
+    labels: Vec<String>,
+    stmt: Option<Stmt>,
+}
+
+fn parse_line(text: &str) -> Result<ParsedLine, String> {
+    let mut parser = Parser {
+        tokens: tokenize(text)?,
+        pos: 0,
+    };
+
+    let mut address = None;
+    if let (Some(&Token::Num(n)), Some(Token::Punct(':'))) = (parser.peek(), parser.peek_at(1)) {
+        address = Some(n);
+        parser.pos += 2;
+    }
+
+    let mut labels = Vec::new();
+    while let (Some(Token::Ident(name)), Some(Token::Punct(':'))) =
+        (parser.peek().cloned(), parser.peek_at(1))
+    {
+        labels.push(name);
+        parser.pos += 2;
+    }
+
+    let stmt = match parser.next() {
+        None => None,
+        Some(Token::Ident(name)) => {
+            if parser.eat('=') {
+                Some(Stmt::Constant(name, parser.expr()?))
+            } else if name.eq_ignore_ascii_case("DATA") || name.eq_ignore_ascii_case("DB") {
+                Some(Stmt::Data(parser.comma_separated(Parser::expr)?))
+            } else {
+                let &(_, code, arity) = OPS
+                    .iter()
+                    .find(|(mnemonic, _, _)| mnemonic.eq_ignore_ascii_case(&name))
+                    .ok_or_else(|| format!("Unrecognized mnemonic {}", name))?;
+
+                let operands = parser.comma_separated(Parser::operand)?;
+                if operands.len() != arity {
+                    return Err(format!(
+                        "{} takes {} operands, got {}",
+                        name,
+                        arity,
+                        operands.len()
+                    ));
+                }
+
+                Some(Stmt::Instruction(code, operands))
+            }
+        }
+        Some(other) => return Err(format!("Expected a mnemonic, got {:?}", other)),
+    };
+
+    if !parser.at_end() {
+        return Err(format!("Unexpected {:?}", parser.peek().unwrap()));
+    }
+
+    Ok(ParsedLine {
+        address,
+        labels,
+        stmt,
+    })
+}
+
+fn eval(expr: &Expr, symbols: &HashMap<String, i64>) -> Result<i64, String> {
+    let overflow = || "Arithmetic overflow".to_string();
+
+    match expr {
+        Expr::Num(n) => Ok(*n),
+        Expr::Name(name) => symbols
+            .get(name)
+            .copied()
+            .ok_or_else(|| format!("Undefined label or constant {}", name)),
+        Expr::Neg(a) => eval(a, symbols)?.checked_neg().ok_or_else(overflow),
+        Expr::Add(a, b) => eval(a, symbols)?
+            .checked_add(eval(b, symbols)?)
+            .ok_or_else(overflow),
+        Expr::Sub(a, b) => eval(a, symbols)?
+            .checked_sub(eval(b, symbols)?)
+            .ok_or_else(overflow),
+        Expr::Mul(a, b) => eval(a, symbols)?
+            .checked_mul(eval(b, symbols)?)
+            .ok_or_else(overflow),
+    }
+}
+
+pub fn assemble(source: &str) -> Result<Vec<i64>, AsmError> {
+    let mut symbols: HashMap<String, i64> = HashMap::new();
+    let mut stmts: Vec<(usize, Stmt)> = Vec::new();
+
+    let define = |symbols: &mut HashMap<String, i64>, name: String, val: i64| {
+        if name.eq_ignore_ascii_case("rb") {
+            return Err("rb is reserved for relative operands".to_string());
+        }
+        if symbols.insert(name.clone(), val).is_some() {
+            return Err(format!("{} is defined more than once", name));
+        }
+        Ok(())
+    };
+
+    // First pass: every statement has a known width, so this pins down all the labels
+    let mut address = 0;
+    for (i, text) in source.lines().enumerate() {
+        let line = i + 1;
+        let err = |message| AsmError { line, message };
+
+        let parsed = parse_line(text).map_err(err)?;
+
+        if let Some(expected) = parsed.address {
+            if expected != address as u64 {
+                return Err(err(format!(
+                    "Line is marked as address {}, but is at address {}",
+                    expected, address
+                )));
+            }
+        }
+
+        for label in parsed.labels {
+            define(&mut symbols, label, address as i64).map_err(err)?;
+        }
+
+        match &parsed.stmt {
+            Some(Stmt::Instruction(_, operands)) => address += 1 + operands.len(),
+            Some(Stmt::Data(words)) => address += words.len(),
+            Some(Stmt::Constant(_, _)) | None => {}
+        }
+
+        if let Some(stmt) = parsed.stmt {
+            stmts.push((line, stmt));
+        }
+    }
+
+    // Constants can use labels and any constant defined above them
+    for (line, stmt) in &stmts {
+        if let Stmt::Constant(name, expr) = stmt {
+            let err = |message| AsmError {
+                line: *line,
+                message,
+            };
+            let val = eval(expr, &symbols).map_err(err)?;
+            define(&mut symbols, name.clone(), val).map_err(err)?;
+        }
+    }
+
+    // Second pass: emit
+    let mut out = Vec::with_capacity(address);
+    for (line, stmt) in &stmts {
+        let err = |message| AsmError {
+            line: *line,
+            message,
+        };
+
+        match stmt {
+            Stmt::Instruction(code, operands) => {
+                let mut op_val = *code;
+                let mut place = 100;
+                let mut params = Vec::with_capacity(operands.len());
+
+                for operand in operands {
+                    let (mode, expr) = match operand {
+                        Operand::Position(e) => (0, e),
+                        Operand::Immediate(e) => (1, e),
+                        Operand::Relative(e) => (2, e),
+                    };
+                    op_val += mode * place;
+                    place *= 10;
+                    params.push(eval(expr, &symbols).map_err(err)?);
+                }
+
+                out.push(op_val);
+                out.extend(params);
+            }
+            Stmt::Data(words) => {
+                for word in words {
+                    out.push(eval(word, &symbols).map_err(err)?);
+                }
+            }
+            Stmt::Constant(_, _) => {}
+        }
+    }
+
+    Ok(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::intcode::disasm::to_listing;
+    use crate::intcode::{str_to_ints, RunResult, VM};
+
+    #[test]
+    fn quine() {
+        // the self-printing program from day 9
+        let source = "
+            start: ARB #1
+            loop:  OUT rb-1
+                   ADD [counter], #1, [counter]
+                   EQ [counter], #end, [done]
+                   JF [done], #start
+                   HLT
+            end:
+            counter = 100
+            done = counter + 1
+        ";
+
+        let code = assemble(source).unwrap();
+        assert_eq!(
+            code,
+            vec![109, 1, 204, -1, 1001, 100, 1, 100, 1008, 100, 16, 101, 1006, 101, 0, 99]
+        );
+
+        let mut vm = VM::new(&code);
+        assert_eq!(vm.run(), Ok(RunResult::Stopped));
+        assert_eq!(vm.get_all_outputs(), code);
+    }
+
+    #[test]
+    fn expressions_and_data() {
+        let source = "
+            n = 2 * (3 + 4) - -1
+            IN [x]
+            MUL [x], #n, [x]
+            OUT [x]
+            hlt
+            x: DB 0, n*n, x
+        ";
+
+        let code = assemble(source).unwrap();
+        assert_eq!(code, vec![3, 9, 1002, 9, 15, 9, 4, 9, 99, 0, 225, 9]);
+
+        let mut vm = VM::new(&code);
+        vm.give_input(3);
+        assert_eq!(vm.run(), Ok(RunResult::Stopped));
+        assert_eq!(vm.get_all_outputs(), vec![45]);
+    }
+
+    #[test]
+    fn errors_have_line_numbers() {
+        let err = assemble("HLT\nADD [1], #2\n").unwrap_err();
+        assert_eq!(err.line, 2);
+
+        let err = assemble("JT #1, #nowhere").unwrap_err();
+        assert_eq!(err.line, 1);
+        assert!(err.message.contains("nowhere"));
+
+        let err = assemble("a: HLT\na: HLT").unwrap_err();
+        assert_eq!(err.line, 2);
+
+        let err = assemble("    3: HLT").unwrap_err();
+        assert_eq!(err.line, 1);
+    }
+
+    #[test]
+    fn round_trip() {
+        let programs = [
+            include_str!("../resources/2a.txt"),
+            include_str!("../resources/5a.txt"),
+            include_str!("../resources/7a.txt"),
+            include_str!("../resources/9a.txt"),
+            include_str!("../resources/13a.txt"),
+            include_str!("../resources/15a.txt"),
+        ];
+
+        for program in programs.iter() {
+            let code = str_to_ints(program);
+            assert_eq!(assemble(&to_listing(&code)), Ok(code));
+        }
+
+        // the extremes, in each kind of operand and as data
+        let code = vec![
+            1101,
+            i64::MIN,
+            i64::MAX,
+            0,
+            209,
+            i64::MIN,
+            1,
+            i64::MIN,
+            i64::MAX,
+            0,
+            99,
+            i64::MIN,
+            i64::MAX,
+        ];
+        let listing = to_listing(&code);
+        assert!(listing.contains("-9223372036854775808"), "{}", listing);
+        assert_eq!(assemble(&listing), Ok(code));
+
+        assert_eq!(
+            assemble("DB -9223372036854775809").unwrap_err().message,
+            "Number -9223372036854775809 is too large"
+        );
+        assert_eq!(
+            assemble("DB 9223372036854775808").unwrap_err().message,
+            "Number 9223372036854775808 is too large"
+        );
+    }
+}
@@ -0,0 +1,509 @@
+use std::collections::BTreeSet;
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, Write};
+
+use super::disasm::disassemble;
+use super::{Access, StepResult, VmError, WatchHit, WatchKind, VM};
+
+// Why the debugger handed control back to the user
+#[derive(Debug, Eq, PartialEq, Copy, Clone)]
+pub enum Pause {
+    // Finished the requested number of steps
+    Stepped,
+    // About to execute the instruction at this address
+    Breakpoint(usize),
+    Output(i64),
+    Watchpoint(WatchHit),
+    NeedInput,
+    Stopped,
+    Fault(VmError),
+}
+
+pub struct Debugger {
+    vm: VM,
+    breakpoints: BTreeSet<usize>,
+}
+
+const HELP: &str = "Commands:
+  s, step [N]         execute N instructions (default 1)
+  c, continue         run until a breakpoint, input is needed, or the program halts
+  o, output           run until the program produces an output
+  sb, stepback [N]    undo the last N instructions (default 1, up to 100000)
+  bt, backto ADDR     undo instructions until just before ADDR was last executed
+  b, break ADDR       set a breakpoint
+  d, delete ADDR      remove a breakpoint
+  bl                  list breakpoints
+  w, watch ADDR [K]   pause when ADDR is accessed; K is r, w (default) or rw
+  uw, unwatch ADDR    remove a watchpoint
+  i, input VAL...     queue input values
+  m, mem ADDR [LEN]   print LEN words of memory starting at ADDR (default 8)
+  l, list [ADDR] [N]  disassemble N instructions starting at ADDR (default ip, 5)
+  save FILE           save a snapshot of the program state
+  load FILE           replace the program state with a saved snapshot
+  r, regs             print ip, relative base and how many steps can be undone
+  h, help             print this message
+  q, quit             exit the debugger";
+
+// The most m will print at once, so a typo can't ask for the whole address space; l
+// lists up to a quarter of this many instructions
+const MAX_WORDS: usize = 1 << 16;
+
+// How many instructions can be undone. Each costs a little memory, so this keeps a long
+// continue from using it all up.
+const HISTORY_DEPTH: usize = 100_000;
+
+impl Debugger {
+    pub fn new(mut vm: VM) -> Self {
+        vm.enable_history_up_to(HISTORY_DEPTH);
+
+        Debugger {
+            vm,
+            breakpoints: BTreeSet::new(),
+        }
+    }
+
+    pub fn add_breakpoint(&mut self, address: usize) {
+        self.breakpoints.insert(address);
+    }
+
+    pub fn remove_breakpoint(&mut self, address: usize) -> bool {
+        self.breakpoints.remove(&address)
+    }
+
+    pub fn give_input(&mut self, input: i64) {
+        self.vm.give_input(input);
+    }
+
+    // Any outputs produced while stepping are left on the VM; see take_outputs
+    pub fn step(&mut self, num_steps: usize) -> Pause {
+        for _ in 0..num_steps {
+            match self.vm.step() {
+                Ok(StepResult::Ran) => {}
+                Ok(StepResult::NeedInput) => return Pause::NeedInput,
+                Ok(StepResult::Stopped) => return Pause::Stopped,
+                Ok(StepResult::Watchpoint(hit)) => return Pause::Watchpoint(hit),
+                Err(e) => return Pause::Fault(e),
+            }
+        }
+
+        if self.vm.is_stopped() {
+            Pause::Stopped
+        } else {
+            Pause::Stepped
+        }
+    }
+
+    pub fn continue_to_breakpoint(&mut self) -> Pause {
+        // Always take at least one step, so continuing from a breakpoint makes progress
+        let mut first = true;
+
+        loop {
+            let ip = self.vm.ip();
+            if !first && self.breakpoints.contains(&ip) {
+                return Pause::Breakpoint(ip);
+            }
+            first = false;
+
+            match self.step(1) {
+                Pause::Stepped => {}
+                other => return other,
+            }
+        }
+    }
+
+    pub fn run_until_output(&mut self) -> Pause {
+        loop {
+            match self.step(1) {
+                Pause::Stepped => {}
+                other => return other,
+            }
+
+            if let Some(output) = self.vm.get_next_output() {
+                return Pause::Output(output);
+            }
+        }
+    }
+
+    pub fn take_outputs(&mut self) -> Vec<i64> {
+        self.vm.get_all_outputs()
+    }
+
+    // Stops short at the end of the address space, rather than wrapping around
+    pub fn memory(&self, start: usize, len: usize) -> Vec<i64> {
+        self.vm.peek_range(start..start.saturating_add(len))
+    }
+
+    pub fn listing(&self, start: usize, num_lines: usize) -> Vec<String> {
+        // every instruction is at most four words, so this window is always enough
+        let window = self.memory(start, num_lines.saturating_mul(4));
+
+        disassemble(&window)
+            .into_iter()
+            .take(num_lines)
+            .map(|mut line| {
+                line.address += start;
+                let marker = if line.address == self.vm.ip() {
+                    ">"
+                } else {
+                    " "
+                };
+                format!("{}{}", marker, line)
+            })
+            .collect()
+    }
+
+    pub fn repl<R: BufRead, W: Write>(&mut self, input: R, mut out: W) -> io::Result<()> {
+        writeln!(out, "Type h for help")?;
+        self.print_location(&mut out)?;
+
+        for line in input.lines() {
+            let line = line?;
+            let tokens = line.split_whitespace().collect::<Vec<&str>>();
+            if tokens.is_empty() {
+                continue;
+            }
+
+            if tokens[0] == "q" || tokens[0] == "quit" {
+                return Ok(());
+            }
+
+            let pause = match self.command(tokens[0], &tokens[1..], &mut out) {
+                Ok(pause) => pause,
+                Err(e) => {
+                    writeln!(out, "{}", e)?;
+                    None
+                }
+            };
+
+            if let Some(pause) = pause {
+                self.print_pause(pause, &mut out)?;
+            }
+        }
+
+        Ok(())
+    }
+
+    // Runs one REPL command; if it ran the program, returns why it stopped
+    fn command<W: Write>(
+        &mut self,
+        name: &str,
+        args: &[&str],
+        out: &mut W,
+    ) -> Result<Option<Pause>, String> {
+        let io_err = |e: io::Error| e.to_string();
+
+        match name {
+            "s" | "step" => {
+                let n = arg_or(args, 0, 1)?;
+                return Ok(Some(self.step(n)));
+            }
+            "c" | "continue" => return Ok(Some(self.continue_to_breakpoint())),
+            "sb" | "stepback" => {
+                let n = arg_or(args, 0, 1)?;
+                let undone = (0..n).take_while(|_| self.vm.step_back()).count();
+                writeln!(out, "Undid {} instructions", undone).map_err(io_err)?;
+                self.print_location(out).map_err(io_err)?;
+            }
+            "bt" | "backto" => {
+                let address = required_arg(args, 0)?;
+                if !self.vm.run_back_to(address) {
+                    writeln!(out, "Ran out of history before reaching {}", address)
+                        .map_err(io_err)?;
+                }
+                self.print_location(out).map_err(io_err)?;
+            }
+            "o" | "output" => return Ok(Some(self.run_until_output())),
+            "b" | "break" => {
+                let address = required_arg(args, 0)?;
+                self.add_breakpoint(address);
+                writeln!(out, "Breakpoint set at {}", address).map_err(io_err)?;
+            }
+            "d" | "delete" => {
+                let address = required_arg(args, 0)?;
+                if self.remove_breakpoint(address) {
+                    writeln!(out, "Breakpoint at {} removed", address).map_err(io_err)?;
+                } else {
+                    writeln!(out, "No breakpoint at {}", address).map_err(io_err)?;
+                }
+            }
+            "w" | "watch" => {
+                let address = required_arg(args, 0)?;
+                let kind = match args.get(1).copied() {
+                    None | Some("w") => WatchKind::Write,
+                    Some("r") => WatchKind::Read,
+                    Some("rw") => WatchKind::ReadWrite,
+                    Some(other) => return Err(format!("Unrecognized watch kind {}", other)),
+                };
+                self.vm.add_watchpoint(address, kind);
+                writeln!(out, "Watching {}", address).map_err(io_err)?;
+            }
+            "uw" | "unwatch" => {
+                let address = required_arg(args, 0)?;
+                if self.vm.remove_watchpoint(address) {
+                    writeln!(out, "Stopped watching {}", address).map_err(io_err)?;
+                } else {
+                    writeln!(out, "Not watching {}", address).map_err(io_err)?;
+                }
+            }
+            "bl" => {
+                for address in &self.breakpoints {
+                    writeln!(out, "  {}", address).map_err(io_err)?;
+                }
+            }
+            "i" | "input" => {
+                let vals = args
+                    .iter()
+                    .map(|a| a.parse::<i64>())
+                    .collect::<Result<Vec<_>, _>>()
+                    .map_err(|_| "Inputs must be integers".to_string())?;
+                for val in vals {
+                    self.give_input(val);
+                }
+            }
+            "m" | "mem" => {
+                let start = required_arg(args, 0)?;
+                let len = arg_or(args, 1, 8)?;
+                check_range(start, len)?;
+                let vals = self.memory(start, len);
+                for (i, chunk) in vals.chunks(8).enumerate() {
+                    let words = chunk.iter().map(|v| v.to_string()).collect::<Vec<_>>();
+                    writeln!(out, "{:>5}: {}", start + i * 8, words.join(" ")).map_err(io_err)?;
+                }
+            }
+            "l" | "list" => {
+                let start = arg_or(args, 0, self.vm.ip())?;
+                let n = arg_or(args, 1, 5)?;
+                check_range(start, n.saturating_mul(4))?;
+                for line in self.listing(start, n) {
+                    writeln!(out, "{}", line).map_err(io_err)?;
+                }
+            }
+            "save" => {
+                let path = args.first().ok_or_else(|| "Need a file name".to_string())?;
+                File::create(path)
+                    .and_then(|file| self.vm.save(file))
+                    .map_err(|e| format!("Could not save to {}: {}", path, e))?;
+                writeln!(out, "Saved to {}", path).map_err(io_err)?;
+            }
+            "load" => {
+                let path = args.first().ok_or_else(|| "Need a file name".to_string())?;
+                let mut vm = File::open(path)
+                    .and_then(|file| VM::load(BufReader::new(file)))
+                    .map_err(|e| format!("Could not load {}: {}", path, e))?;
+                vm.enable_history_up_to(HISTORY_DEPTH);
+                self.vm = vm;
+                writeln!(out, "Loaded {}", path).map_err(io_err)?;
+                self.print_location(out).map_err(io_err)?;
+            }
+            "r" | "regs" => {
+                writeln!(
+                    out,
+                    "ip: {}, relative base: {}, executed: {} steps, history: {} steps",
+                    self.vm.ip(),
+                    self.vm.relative_base(),
+                    self.vm.total_steps(),
+                    self.vm.history_len()
+                )
+                .map_err(io_err)?;
+            }
+            "h" | "help" => writeln!(out, "{}", HELP).map_err(io_err)?,
+            other => return Err(format!("Unrecognized command {}; type h for help", other)),
+        }
+
+        Ok(None)
+    }
+
+    fn print_pause<W: Write>(&mut self, pause: Pause, out: &mut W) -> io::Result<()> {
+        // outputs from steps and continues are left on the VM, so show them here
+        for output in self.take_outputs() {
+            writeln!(out, "Output: {}", output)?;
+        }
+
+        match pause {
+            Pause::Stepped => {}
+            Pause::Breakpoint(address) => writeln!(out, "Hit breakpoint at {}", address)?,
+            Pause::Output(output) => writeln!(out, "Output: {}", output)?,
+            Pause::Watchpoint(hit) => match hit.access {
+                Access::Read => writeln!(
+                    out,
+                    "Instruction at {} read {} from {}",
+                    hit.ip, hit.old_val, hit.address
+                )?,
+                Access::Write => writeln!(
+                    out,
+                    "Instruction at {} wrote {} to {} (was {})",
+                    hit.ip, hit.new_val, hit.address, hit.old_val
+                )?,
+            },
+            Pause::NeedInput => writeln!(out, "Waiting for input")?,
+            Pause::Stopped => {
+                writeln!(out, "Program halted")?;
+                return Ok(());
+            }
+            Pause::Fault(e) => writeln!(out, "Fault: {}", e)?,
+        }
+
+        self.print_location(out)
+    }
+
+    fn print_location<W: Write>(&self, out: &mut W) -> io::Result<()> {
+        if let Some(line) = self.listing(self.vm.ip(), 1).pop() {
+            writeln!(out, "{}", line)?;
+        }
+        Ok(())
+    }
+}
+
+fn arg_or(args: &[&str], index: usize, default: usize) -> Result<usize, String> {
+    match args.get(index) {
+        None => Ok(default),
+        Some(arg) => arg
+            .parse()
+            .map_err(|_| format!("Could not parse argument {}", arg)),
+    }
+}
+
+fn check_range(start: usize, len: usize) -> Result<(), String> {
+    if len > MAX_WORDS {
+        return Err(format!("Can only show {} words at once", MAX_WORDS));
+    }
+    match start.checked_add(len) {
+        Some(_) => Ok(()),
+        None => Err(format!("{} is too close to the end of memory", start)),
+    }
+}
+
+fn required_arg(args: &[&str], index: usize) -> Result<usize, String> {
+    if index >= args.len() {
+        return Err("Need an address".to_string());
+    }
+    arg_or(args, index, 0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::intcode::asm::assemble;
+
+    fn doubler() -> VM {
+        let code = assemble(
+            "
+            loop: IN [x]
+                  MUL [x], #2, [x]
+                  OUT [x]
+                  JT #1, #loop
+            x:    DATA 0
+            ",
+        )
+        .unwrap();
+
+        VM::new(&code)
+    }
+
+    #[test]
+    fn breakpoints_and_output() {
+        let mut debugger = Debugger::new(doubler());
+        debugger.add_breakpoint(6);
+
+        assert_eq!(debugger.continue_to_breakpoint(), Pause::NeedInput);
+
+        debugger.give_input(5);
+        debugger.give_input(7);
+        assert_eq!(debugger.continue_to_breakpoint(), Pause::Breakpoint(6));
+        assert!(debugger.take_outputs().is_empty());
+        assert_eq!(debugger.memory(11, 1), vec![10]);
+
+        assert_eq!(debugger.run_until_output(), Pause::Output(10));
+        assert_eq!(debugger.continue_to_breakpoint(), Pause::Breakpoint(6));
+
+        assert!(debugger.remove_breakpoint(6));
+        assert_eq!(debugger.continue_to_breakpoint(), Pause::NeedInput);
+        assert_eq!(debugger.take_outputs(), vec![14]);
+    }
+
+    #[test]
+    fn step_reports_faults() {
+        let mut debugger = Debugger::new(VM::new(&[1101, 1, 1, 5, 77, 0]));
+
+        assert_eq!(debugger.step(1), Pause::Stepped);
+        match debugger.step(1) {
+            Pause::Fault(e) => assert_eq!(e.ip(), 4),
+            other => panic!("Expected a fault, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn repl_session() {
+        let mut debugger = Debugger::new(doubler());
+        let script = "b 6\ni 21\nc\nr\nm 11 1\no\nbogus\nw 11\ni 4\nc\nq\nc\n";
+
+        let mut out = Vec::new();
+        debugger.repl(script.as_bytes(), &mut out).unwrap();
+        let out = String::from_utf8(out).unwrap();
+
+        assert!(out.contains(">    0: IN [11]"));
+        assert!(out.contains("Breakpoint set at 6"));
+        assert!(out.contains("Hit breakpoint at 6"));
+        assert!(out.contains("ip: 6, relative base: 0, executed: 2 steps, history: 2 steps"));
+        assert!(out.contains("   11: 42"));
+        assert!(out.contains("Output: 42"));
+        assert!(out.contains("Unrecognized command bogus"));
+        assert!(out.contains("Instruction at 0 wrote 4 to 11 (was 42)"));
+        assert!(out.contains(">    8: JT #1, #0"));
+        // nothing runs after quitting
+        assert!(!out.contains("Waiting for input"));
+    }
+
+    #[test]
+    fn bad_ranges() {
+        let mut debugger = Debugger::new(doubler());
+        let max = usize::MAX.to_string();
+        let script = format!(
+            "m {0} 8\nm 0 {0}\nl {0} 2\nl 0 {0}\nm {1} 1\n",
+            max,
+            usize::MAX - 1
+        );
+
+        let mut out = Vec::new();
+        debugger.repl(script.as_bytes(), &mut out).unwrap();
+        let out = String::from_utf8(out).unwrap();
+
+        let too_close = format!("{} is too close to the end of memory", max);
+        assert_eq!(out.matches(&too_close as &str).count(), 2);
+        assert_eq!(out.matches("Can only show 65536 words at once").count(), 2);
+        assert!(out.contains(&format!("{}: 0", usize::MAX - 1)));
+
+        // jumping far away still shows where the program is
+        let mut debugger = Debugger::new(VM::new(&[1105, 1, i64::MAX]));
+        assert_eq!(debugger.step(1), Pause::Stepped);
+        assert_eq!(debugger.listing(debugger.vm.ip(), 1).len(), 1);
+        assert_eq!(debugger.memory(usize::MAX, 8), vec![]);
+    }
+
+    #[test]
+    fn stepping_back() {
+        let mut debugger = Debugger::new(doubler());
+        let script = "i 3\no\nm 11 1\nsb 2\nm 11 1\nbt 0\nm 11 1\nsb\no\n";
+
+        let mut out = Vec::new();
+        debugger.repl(script.as_bytes(), &mut out).unwrap();
+        let out = String::from_utf8(out).unwrap();
+
+        assert!(out.contains("   11: 6"));
+        assert!(out.contains("Undid 2 instructions"));
+        assert!(out.contains(">    2: MUL [11], #2, [11]"));
+        assert!(out.contains("   11: 3"));
+        assert!(out.contains("   11: 0"));
+        assert!(out.contains("Undid 0 instructions"));
+        // the input was put back, so the output is the same the second time
+        assert_eq!(out.matches("Output: 6").count(), 2);
+    }
+
+    #[test]
+    fn history_is_capped() {
+        // loops forever
+        let mut debugger = Debugger::new(VM::new(&[1105, 1, 0]));
+        assert_eq!(debugger.step(HISTORY_DEPTH + 10), Pause::Stepped);
+        assert_eq!(debugger.vm.history_len(), HISTORY_DEPTH);
+    }
+}
@@ -0,0 +1,184 @@
+use std::fmt;
+
+use super::{skip, to_op, Op, ParameterMode};
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Line {
+    pub address: usize,
+    pub item: Item,
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum Item {
+    // The decoded op, and the raw parameter words that follow it
+    Instruction(Op, Vec<i64>),
+    // Anything that doesn't decode, or doesn't fit in what's left of the program
+    Data(i64),
+}
+
+pub fn mnemonic(op: Op) -> &'static str {
+    use Op::*;
+
+    match op {
+        Add(_, _, _) => "ADD",
+        Multiply(_, _, _) => "MUL",
+        TakeInput(_) => "IN",
+        DoOutput(_) => "OUT",
+        JumpIfTrue(_, _) => "JT",
+        JumpIfFalse(_, _) => "JF",
+        LessThan(_, _, _) => "LT",
+        Equals(_, _, _) => "EQ",
+        AdjustRelBase(_) => "ARB",
+        Stop => "HLT",
+    }
+}
+
+pub fn modes(op: Op) -> Vec<ParameterMode> {
+    use Op::*;
+
+    match op {
+        Add(a, b, c) | Multiply(a, b, c) | LessThan(a, b, c) | Equals(a, b, c) => vec![a, b, c],
+        JumpIfTrue(a, b) | JumpIfFalse(a, b) => vec![a, b],
+        TakeInput(a) | DoOutput(a) | AdjustRelBase(a) => vec![a],
+        Stop => vec![],
+    }
+}
+
+// The canonical word for an op; to_op ignores extra mode digits (99999 is still HLT),
+// but those words don't survive a round trip, so they're listed as data instead
+pub fn encode(op: Op) -> i64 {
+    use Op::*;
+
+    let code = match op {
+        Add(_, _, _) => 1,
+        Multiply(_, _, _) => 2,
+        TakeInput(_) => 3,
+        DoOutput(_) => 4,
+        JumpIfTrue(_, _) => 5,
+        JumpIfFalse(_, _) => 6,
+        LessThan(_, _, _) => 7,
+        Equals(_, _, _) => 8,
+        AdjustRelBase(_) => 9,
+        Stop => 99,
+    };
+
+    let mut place = 100;
+    let mut out = code;
+    for mode in modes(op) {
+        let digit = match mode {
+            ParameterMode::Position => 0,
+            ParameterMode::Immediate => 1,
+            ParameterMode::Relative => 2,
+        };
+        out += digit * place;
+        place *= 10;
+    }
+
+    out
+}
+
+pub fn operand_to_string(mode: ParameterMode, val: i64) -> String {
+    match mode {
+        ParameterMode::Position => format!("[{}]", val),
+        ParameterMode::Immediate => format!("#{}", val),
+        ParameterMode::Relative if val < 0 => format!("rb{}", val),
+        ParameterMode::Relative => format!("rb+{}", val),
+    }
+}
+
+impl fmt::Display for Item {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Item::Data(val) => write!(f, "DATA {}", val),
+            Item::Instruction(op, params) => {
+                write!(f, "{}", mnemonic(*op))?;
+
+                for (i, (&mode, &val)) in modes(*op).iter().zip(params.iter()).enumerate() {
+                    let sep = if i == 0 { " " } else { ", " };
+                    write!(f, "{}{}", sep, operand_to_string(mode, val))?;
+                }
+
+                Ok(())
+            }
+        }
+    }
+}
+
+impl fmt::Display for Line {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{:>5}: {}", self.address, self.item)
+    }
+}
+
+pub fn disassemble(code: &[i64]) -> Vec<Line> {
+    let mut out = Vec::new();
+    let mut address = 0;
+
+    while address < code.len() {
+        let decoded = to_op(code[address])
+            .ok()
+            .filter(|&op| encode(op) == code[address])
+            .filter(|&op| address + skip(op) <= code.len());
+
+        let item = match decoded {
+            Some(op) => {
+                let params = code[address + 1..address + skip(op)].to_vec();
+                Item::Instruction(op, params)
+            }
+            None => Item::Data(code[address]),
+        };
+
+        let width = match &item {
+            Item::Instruction(op, _) => skip(*op),
+            Item::Data(_) => 1,
+        };
+
+        out.push(Line { address, item });
+        address += width;
+    }
+
+    out
+}
+
+pub fn to_listing(code: &[i64]) -> String {
+    let mut out = String::new();
+
+    for line in disassemble(code) {
+        out.push_str(&line.to_string());
+        out.push('\n');
+    }
+
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn simple_listing() {
+        let code = [1002, 4, 3, 4, 33, 109, -2, 204, 1, 99];
+
+        let expected = "    0: MUL [4], #3, [4]
+    4: DATA 33
+    5: ARB #-2
+    7: OUT rb+1
+    9: HLT
+";
+
+        assert_eq!(to_listing(&code), expected);
+    }
+
+    #[test]
+    fn truncated_or_odd_instruction_is_data() {
+        let code = [99, 1104, 1, 0, 0];
+
+        let lines = disassemble(&code);
+
+        assert_eq!(lines.len(), 5);
+        assert_eq!(lines[0].item.to_string(), "HLT");
+        for line in &lines[1..] {
+            assert!(matches!(line.item, Item::Data(_)));
+        }
+    }
+}
@@ -0,0 +1,332 @@
+use std::fmt;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{channel, Receiver, RecvTimeoutError};
+use std::thread;
+
+use super::activity::{Activity, QUIET_CHECK};
+use super::io::{from_channel, to_channel, InputSource, OutputSink};
+use super::memory::{DenseMemory, Memory};
+use super::{RunResult, VmError, VM};
+
+// VMs wired together, each output going on as input to every node it's connected to.
+// Connections are directed and can make any shape: chains, rings, fan-out (each target gets
+// a copy) and fan-in (inputs arrive in the order they were sent).
+
+#[derive(Debug, Eq, PartialEq, Copy, Clone, Hash)]
+pub struct NodeId(usize);
+
+#[derive(Debug, Eq, PartialEq, Clone)]
+pub enum GraphError {
+    Fault { node: String, error: VmError },
+    // Nothing can run, but not every node has stopped
+    Deadlock { waiting: Vec<String> },
+}
+
+impl fmt::Display for GraphError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            GraphError::Fault { node, error } => write!(f, "Node {} faulted: {}", node, error),
+            GraphError::Deadlock { waiting } => write!(
+                f,
+                "Deadlock: {} waiting for input that will never come",
+                waiting.join(", ")
+            ),
+        }
+    }
+}
+
+impl std::error::Error for GraphError {}
+
+#[derive(Clone)]
+struct Node<M: Memory> {
+    name: String,
+    vm: VM<M>,
+    targets: Vec<usize>,
+    outputs: Vec<i64>,
+    stopped: bool,
+}
+
+#[derive(Clone)]
+pub struct Graph<M: Memory = DenseMemory> {
+    nodes: Vec<Node<M>>,
+}
+
+impl Graph {
+    pub fn new() -> Self {
+        Graph::default()
+    }
+}
+
+impl<M: Memory> Default for Graph<M> {
+    fn default() -> Self {
+        Graph { nodes: Vec::new() }
+    }
+}
+
+impl<M: Memory> Graph<M> {
+    // The name is only used to report errors
+    pub fn add_node(&mut self, name: &str, vm: VM<M>) -> NodeId {
+        self.nodes.push(Node {
+            name: name.to_string(),
+            vm,
+            targets: Vec::new(),
+            outputs: Vec::new(),
+            stopped: false,
+        });
+        NodeId(self.nodes.len() - 1)
+    }
+
+    // Queues input for a node ahead of anything sent to it along connections, e.g. a phase
+    pub fn give_input(&mut self, node: NodeId, val: i64) {
+        self.nodes[node.0].vm.give_input(val);
+    }
+
+    pub fn connect(&mut self, from: NodeId, to: NodeId) {
+        self.nodes[from.0].targets.push(to.0);
+    }
+
+    // Everything the node has output so far, whether or not it's connected to anything
+    pub fn outputs(&self, node: NodeId) -> &[i64] {
+        &self.nodes[node.0].outputs
+    }
+
+    // Instructions executed by every node, so far
+    pub fn total_steps(&self) -> u64 {
+        self.nodes.iter().map(|node| node.vm.total_steps()).sum()
+    }
+
+    // Runs each node in turn, passing on outputs as they come, until every node stops
+    pub fn run(&mut self) -> Result<(), GraphError> {
+        loop {
+            let mut progress = false;
+
+            for i in 0..self.nodes.len() {
+                let node = &mut self.nodes[i];
+                if node.stopped {
+                    continue;
+                }
+
+                let result = node.vm.run().map_err(|error| GraphError::Fault {
+                    node: node.name.clone(),
+                    error,
+                })?;
+                node.stopped = result == RunResult::Stopped;
+                progress |= node.vm.last_run_steps() > 0;
+
+                let outputs = node.vm.get_all_outputs();
+                node.outputs.extend(&outputs);
+                for target in node.targets.clone() {
+                    for &val in &outputs {
+                        self.nodes[target].vm.give_input(val);
+                    }
+                }
+            }
+
+            if self.nodes.iter().all(|node| node.stopped) {
+                return Ok(());
+            }
+            if !progress {
+                return Err(self.deadlock());
+            }
+        }
+    }
+
+    fn deadlock(&self) -> GraphError {
+        let waiting = self
+            .nodes
+            .iter()
+            .filter(|node| !node.stopped)
+            .map(|node| node.name.clone())
+            .collect();
+        GraphError::Deadlock { waiting }
+    }
+}
+
+impl<M: Memory + Send> Graph<M> {
+    // Like run, but with each node on its own thread, reading from a channel
+    pub fn run_threaded(&mut self) -> Result<(), GraphError> {
+        let size = self.nodes.len();
+        let activity = Activity::new(size);
+        // set once the run is over, so nodes still waiting give up
+        let abort = AtomicBool::new(false);
+        let (senders, receivers): (Vec<_>, Vec<_>) = (0..size).map(|_| channel()).unzip();
+        let (done, finished) = channel();
+
+        let fault = thread::scope(|scope| {
+            for ((i, node), receiver) in self.nodes.iter_mut().enumerate().zip(receivers) {
+                let targets = node
+                    .targets
+                    .iter()
+                    .map(|&target| (target, to_channel(senders[target].clone())))
+                    .collect();
+                let (activity, abort) = (&activity, &abort);
+                let done = done.clone();
+
+                scope.spawn(move || {
+                    let result = run_node(i, node, receiver, targets, activity, abort);
+                    activity.finish(i);
+                    let _ = done.send((i, result));
+                });
+            }
+            drop(done);
+
+            let fault = wait_for_nodes(&activity, &finished, size);
+
+            // wake anything still waiting, so it sees the abort and ends
+            abort.store(true, Ordering::SeqCst);
+            for sender in &senders {
+                let _ = sender.send(0);
+            }
+            fault
+        });
+
+        if let Some((i, error)) = fault {
+            return Err(GraphError::Fault {
+                node: self.nodes[i].name.clone(),
+                error,
+            });
+        }
+        if self.nodes.iter().all(|node| node.stopped) {
+            Ok(())
+        } else {
+            Err(self.deadlock())
+        }
+    }
+}
+
+// Waits until every node finishes, one faults, or they deadlock
+fn wait_for_nodes(
+    activity: &Activity,
+    finished: &Receiver<(usize, Result<RunResult, VmError>)>,
+    size: usize,
+) -> Option<(usize, VmError)> {
+    let mut running = size;
+    while running > 0 {
+        match finished.recv_timeout(QUIET_CHECK) {
+            Ok((i, Err(error))) => return Some((i, error)),
+            Ok((_, Ok(_))) => running -= 1,
+            Err(RecvTimeoutError::Timeout) if activity.quiet() => return None,
+            Err(RecvTimeoutError::Timeout) => {}
+            Err(RecvTimeoutError::Disconnected) => return None,
+        }
+    }
+    None
+}
+
+fn run_node<M: Memory, O: OutputSink>(
+    i: usize,
+    node: &mut Node<M>,
+    receiver: Receiver<i64>,
+    mut targets: Vec<(usize, O)>,
+    activity: &Activity,
+    abort: &AtomicBool,
+) -> Result<RunResult, VmError> {
+    let mut channel = from_channel(receiver);
+    let mut input = || {
+        if abort.load(Ordering::SeqCst) {
+            return None;
+        }
+        activity.set_waiting(i, true);
+        let val = channel.next_input();
+        activity.set_waiting(i, false);
+        if abort.load(Ordering::SeqCst) {
+            return None;
+        }
+
+        activity.received(i);
+        val
+    };
+
+    let outputs = &mut node.outputs;
+    let mut output = |val| {
+        outputs.push(val);
+        for (target, sink) in &mut targets {
+            activity.sent(*target);
+            sink.send(val);
+        }
+    };
+
+    let result = node.vm.run_with_io(&mut input, &mut output);
+    node.stopped = result == Ok(RunResult::Stopped);
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // out = in
+    const ECHO: [i64; 6] = [3, 5, 4, 5, 99, 0];
+    // out = in * 2
+    const DOUBLE: [i64; 10] = [3, 9, 1002, 9, 2, 9, 4, 9, 99, 0];
+    // out = in1 + in2
+    const ADD: [i64; 14] = [3, 11, 3, 12, 1, 11, 12, 13, 4, 13, 99, 0, 0, 0];
+
+    #[test]
+    fn fan_out_and_in() {
+        let mut graph = Graph::new();
+        let fork = graph.add_node("fork", VM::new(&ECHO));
+        let double = graph.add_node("double", VM::new(&DOUBLE));
+        let add = graph.add_node("add", VM::new(&ADD));
+
+        // add gets 5 straight from fork, then 10 via double
+        graph.give_input(fork, 5);
+        graph.connect(fork, double);
+        graph.connect(fork, add);
+        graph.connect(double, add);
+
+        let mut threaded = graph.clone();
+        assert_eq!(graph.run(), Ok(()));
+        assert_eq!(threaded.run_threaded(), Ok(()));
+        assert_eq!(threaded.outputs(add), &[15]);
+        assert_eq!(graph.outputs(fork), &[5]);
+        assert_eq!(graph.outputs(double), &[10]);
+        assert_eq!(graph.outputs(add), &[15]);
+    }
+
+    #[test]
+    fn deadlock() {
+        let mut graph = Graph::new();
+        let first = graph.add_node("first", VM::new(&ADD));
+        let second = graph.add_node("second", VM::new(&ADD));
+        let third = graph.add_node("third", VM::new(&DOUBLE));
+        graph.connect(first, second);
+        graph.connect(second, first);
+
+        graph.give_input(first, 1);
+        graph.give_input(second, 2);
+        graph.give_input(third, 3);
+
+        let mut threaded = graph.clone();
+        let err = graph.run().unwrap_err();
+        assert_eq!(threaded.run_threaded(), Err(err.clone()));
+        assert_eq!(threaded.outputs(third), &[6]);
+
+        assert_eq!(
+            err,
+            GraphError::Deadlock {
+                waiting: vec!["first".to_string(), "second".to_string()]
+            }
+        );
+        assert_eq!(
+            err.to_string(),
+            "Deadlock: first, second waiting for input that will never come"
+        );
+        assert_eq!(graph.outputs(third), &[6]);
+    }
+
+    #[test]
+    fn faults_name_the_node() {
+        let mut graph = Graph::new();
+        graph.add_node("ok", VM::new(&[99]));
+        graph.add_node("bad", VM::new(&[42]));
+
+        let mut threaded = graph.clone();
+        for result in [graph.run(), threaded.run_threaded()] {
+            match result {
+                Err(GraphError::Fault { node, .. }) => assert_eq!(node, "bad"),
+                other => panic!("Expected a fault, got {:?}", other),
+            }
+        }
+    }
+}
@@ -0,0 +1,209 @@
+use std::collections::VecDeque;
+
+use super::memory::Memory;
+use super::VM;
+
+// Enough to undo one instruction. The VM is deterministic, so this is all that changes;
+// the VM wasn't stopped before any instruction, so that doesn't need saving either.
+#[derive(Clone, Debug)]
+pub(super) struct UndoEntry {
+    pub(super) ip: usize,
+    pub(super) relative_base: i64,
+    // (address, old value), in the order they were written
+    pub(super) writes: Vec<(usize, i64)>,
+    pub(super) input: Option<i64>,
+    pub(super) produced_output: bool,
+}
+
+// The undo log, oldest first. Past its limit the oldest entries are dropped, so only
+// that many instructions can be undone.
+#[derive(Clone, Debug)]
+pub(super) struct History {
+    entries: VecDeque<UndoEntry>,
+    limit: usize,
+}
+
+impl History {
+    pub(super) fn push(&mut self, entry: UndoEntry) {
+        self.entries.push_back(entry);
+        self.trim();
+    }
+
+    fn trim(&mut self) {
+        while self.entries.len() > self.limit {
+            self.entries.pop_front();
+        }
+    }
+}
+
+impl<M: Memory> VM<M> {
+    // Starts recording an undo log, so the VM can be stepped backward. This costs memory
+    // for every instruction executed, so it's off by default.
+    pub fn enable_history(&mut self) {
+        self.enable_history_up_to(usize::MAX);
+    }
+
+    // As enable_history, but only keeping the last max_steps instructions, so memory use
+    // is bounded however long the program runs. Also changes the limit if already enabled.
+    pub fn enable_history_up_to(&mut self, max_steps: usize) {
+        let history = self.history.get_or_insert_with(|| History {
+            entries: VecDeque::new(),
+            limit: max_steps,
+        });
+        history.limit = max_steps;
+        history.trim();
+    }
+
+    pub fn history_len(&self) -> usize {
+        self.history.as_ref().map(|h| h.entries.len()).unwrap_or(0)
+    }
+
+    // Undoes the last executed instruction, which no longer counts towards total_steps;
+    // returns false if there's nothing to undo.
+    //
+    // Consumed inputs go back on the front of the input queue. Outputs are taken back off
+    // the output queue, but anything the caller already took is gone for good.
+    pub fn step_back(&mut self) -> bool {
+        let entry = match self.history.as_mut().and_then(|h| h.entries.pop_back()) {
+            Some(entry) => entry,
+            None => return false,
+        };
+
+        for &(address, old_val) in entry.writes.iter().rev() {
+            self.set_memory(address, old_val);
+        }
+
+        if let Some(input) = entry.input {
+            self.stored_inputs.push_front(input);
+        }
+
+        if entry.produced_output {
+            self.stored_outputs.pop_back();
+        }
+
+        self.ip = entry.ip;
+        self.relative_base = entry.relative_base;
+        self.stopped = false;
+        self.total_steps -= 1;
+
+        true
+    }
+
+    // Steps back (at least once) until the ip is at the given address, that is, to just
+    // before the most recent time the instruction there was executed. Returns false if the
+    // history ran out first, leaving the VM at the oldest recorded state.
+    pub fn run_back_to(&mut self, address: usize) -> bool {
+        loop {
+            if !self.step_back() {
+                return false;
+            }
+
+            if self.ip == address {
+                return true;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::intcode::{str_to_ints, RunResult, VM};
+
+    fn snapshot(vm: &VM, len: usize) -> (usize, i64, bool, u64, Vec<i64>) {
+        let memory = vm.peek_range(0..len);
+        let steps = vm.total_steps();
+        (vm.ip(), vm.relative_base(), vm.is_stopped(), steps, memory)
+    }
+
+    #[test]
+    fn step_back_restores_every_state() {
+        let code = str_to_ints(include_str!("../resources/9a.txt"));
+        let len = code.len() + 100;
+
+        let mut vm = VM::new(&code);
+        vm.enable_history();
+        vm.give_input(1);
+
+        let mut states = vec![snapshot(&vm, len)];
+        while !vm.is_stopped() {
+            vm.step().unwrap();
+            states.push(snapshot(&vm, len));
+        }
+        assert_eq!(vm.history_len(), states.len() - 1);
+
+        let output = vm.get_all_outputs();
+        assert_eq!(output, vec![2738720997]);
+
+        states.pop();
+        while let Some(state) = states.pop() {
+            assert!(vm.step_back());
+            assert_eq!(snapshot(&vm, len), state);
+        }
+        assert!(!vm.step_back());
+
+        // the input was given back, so it all plays out the same way again
+        assert_eq!(vm.run(), Ok(RunResult::Stopped));
+        assert_eq!(vm.get_all_outputs(), output);
+    }
+
+    #[test]
+    fn run_back_to_address() {
+        // x = in; out x; x += 1; out x; halt
+        let code = [3, 11, 4, 11, 1001, 11, 1, 11, 4, 11, 99, 0];
+
+        let mut vm = VM::new(&code);
+        vm.enable_history();
+        vm.give_input(41);
+        assert_eq!(vm.run(), Ok(RunResult::Stopped));
+
+        // just before the second output
+        assert_eq!(vm.total_steps(), 5);
+        assert!(vm.run_back_to(8));
+        assert_eq!(vm.ip(), 8);
+        assert_eq!(vm.total_steps(), 3);
+        assert_eq!(vm.peek(11), 42);
+        assert!(!vm.is_stopped());
+        assert_eq!(vm.get_all_outputs(), vec![41]);
+
+        assert!(vm.run_back_to(0));
+        assert_eq!(vm.peek(11), 0);
+        assert_eq!(vm.total_steps(), 0);
+
+        assert!(!vm.run_back_to(4));
+        assert_eq!(vm.ip(), 0);
+    }
+
+    #[test]
+    fn limited_history() {
+        // x += 1, forever
+        let code = [1001, 7, 1, 7, 1105, 1, 0, 0];
+
+        let mut vm = VM::new(&code);
+        vm.enable_history_up_to(10);
+        for _ in 0..100 {
+            vm.step().unwrap();
+        }
+        assert_eq!(vm.history_len(), 10);
+
+        let undone = (0..100).take_while(|_| vm.step_back()).count();
+        assert_eq!(undone, 10);
+        assert_eq!(vm.total_steps(), 90);
+        assert_eq!(vm.peek(7), 45);
+
+        // lowering the limit drops the oldest entries straight away
+        for _ in 0..10 {
+            vm.step().unwrap();
+        }
+        vm.enable_history_up_to(4);
+        assert_eq!(vm.history_len(), 4);
+    }
+
+    #[test]
+    fn no_history_by_default() {
+        let mut vm = VM::new(&[104, 1, 99]);
+        assert_eq!(vm.run(), Ok(RunResult::Stopped));
+
+        assert_eq!(vm.history_len(), 0);
+        assert!(!vm.step_back());
+    }
+}
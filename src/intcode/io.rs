@@ -0,0 +1,337 @@
+use std::collections::VecDeque;
+use std::io::{self, BufRead, Write};
+use std::sync::mpsc::{Receiver, Sender};
+
+use super::memory::Memory;
+use super::{RunResult, VmError, VM};
+
+// Where a VM gets its input when driven by run_with_io
+pub trait InputSource {
+    // None if there's no input (yet); the VM then pauses with NeedInput
+    fn next_input(&mut self) -> Option<i64>;
+
+    // Called once the caller is done; sources that read from somewhere should report any
+    // errors here, since next_input has nowhere to send them
+    fn finish(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+// Where a VM's outputs go when driven by run_with_io
+pub trait OutputSink {
+    fn send(&mut self, val: i64);
+
+    // As for InputSource
+    fn finish(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+impl<F: FnMut() -> Option<i64>> InputSource for F {
+    fn next_input(&mut self) -> Option<i64> {
+        self()
+    }
+}
+
+impl<F: FnMut(i64)> OutputSink for F {
+    fn send(&mut self, val: i64) {
+        self(val)
+    }
+}
+
+pub fn from_iter<I: IntoIterator<Item = i64>>(vals: I) -> impl InputSource {
+    let mut vals = vals.into_iter();
+    move || vals.next()
+}
+
+// Blocks until something is sent, so this suits a VM on its own thread; runs out once
+// every sender is gone
+pub fn from_channel(receiver: Receiver<i64>) -> impl InputSource {
+    move || receiver.recv().ok()
+}
+
+// Outputs sent after the receiver is gone are dropped
+pub fn to_channel(sender: Sender<i64>) -> impl OutputSink {
+    move |val| {
+        let _ = sender.send(val);
+    }
+}
+
+// Numbers separated by commas or whitespace, read a line at a time as they're needed, so
+// this works for interactive input too
+pub struct ReaderSource<R: BufRead> {
+    reader: R,
+    pending: VecDeque<i64>,
+    error: Option<io::Error>,
+}
+
+impl<R: BufRead> ReaderSource<R> {
+    pub fn new(reader: R) -> Self {
+        ReaderSource {
+            reader,
+            pending: VecDeque::new(),
+            error: None,
+        }
+    }
+
+    fn read_line(&mut self) -> io::Result<bool> {
+        let mut line = String::new();
+        if self.reader.read_line(&mut line)? == 0 {
+            return Ok(false);
+        }
+
+        for token in line.split(|c: char| c == ',' || c.is_whitespace()) {
+            if token.is_empty() {
+                continue;
+            }
+            let val = token.parse().map_err(|_| {
+                io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!("Not a number: {:?}", token),
+                )
+            })?;
+            self.pending.push_back(val);
+        }
+
+        Ok(true)
+    }
+}
+
+impl<R: BufRead> InputSource for ReaderSource<R> {
+    fn next_input(&mut self) -> Option<i64> {
+        while self.pending.is_empty() && self.error.is_none() {
+            match self.read_line() {
+                Ok(true) => {}
+                Ok(false) => break,
+                Err(e) => self.error = Some(e),
+            }
+        }
+
+        self.pending.pop_front()
+    }
+
+    fn finish(&mut self) -> io::Result<()> {
+        match self.error.take() {
+            Some(e) => Err(e),
+            None => Ok(()),
+        }
+    }
+}
+
+// One output per line
+pub struct WriterSink<W: Write> {
+    out: W,
+    error: Option<io::Error>,
+}
+
+impl<W: Write> WriterSink<W> {
+    pub fn new(out: W) -> Self {
+        WriterSink { out, error: None }
+    }
+}
+
+impl<W: Write> OutputSink for WriterSink<W> {
+    fn send(&mut self, val: i64) {
+        if self.error.is_none() {
+            if let Err(e) = writeln!(self.out, "{}", val) {
+                self.error = Some(e);
+            }
+        }
+    }
+
+    fn finish(&mut self) -> io::Result<()> {
+        if let Some(e) = self.error.take() {
+            return Err(e);
+        }
+        self.out.flush()
+    }
+}
+
+impl<M: Memory> VM<M> {
+    // Runs until the program stops, or needs input the source doesn't have (yet). Outputs
+    // are passed on whenever the VM pauses, so always before it waits for more input.
+    // Afterwards last_run_steps covers this whole call.
+    pub fn run_with_io<I, O>(&mut self, input: &mut I, output: &mut O) -> Result<RunResult, VmError>
+    where
+        I: InputSource + ?Sized,
+        O: OutputSink + ?Sized,
+    {
+        let steps_before = self.total_steps;
+        let result = self.pump(input, output);
+        self.last_run_steps = self.total_steps - steps_before;
+        result
+    }
+
+    fn pump<I, O>(&mut self, input: &mut I, output: &mut O) -> Result<RunResult, VmError>
+    where
+        I: InputSource + ?Sized,
+        O: OutputSink + ?Sized,
+    {
+        loop {
+            let result = self.run();
+            while let Some(val) = self.get_next_output() {
+                output.send(val);
+            }
+
+            match result? {
+                RunResult::NeedInput => match input.next_input() {
+                    Some(val) => self.give_input(val),
+                    None => return Ok(RunResult::NeedInput),
+                },
+                other => return Ok(other),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::mpsc::channel;
+    use std::thread;
+
+    // out = in * 2, until it reads a 0
+    const DOUBLER: [i64; 16] = [
+        3, 15, 1006, 15, 14, 1002, 15, 2, 15, 4, 15, 1105, 1, 0, 99, 0,
+    ];
+
+    #[test]
+    fn closures_and_iterators() {
+        let mut vm = VM::new(&DOUBLER);
+        let mut outputs = Vec::new();
+
+        let result = vm.run_with_io(&mut from_iter(vec![1, 2, 3]), &mut |v| outputs.push(v));
+        assert_eq!(result, Ok(RunResult::NeedInput));
+        assert_eq!(outputs, vec![2, 4, 6]);
+
+        let mut inputs = vec![0, 5];
+        let result = vm.run_with_io(&mut || inputs.pop(), &mut |v| outputs.push(v));
+        assert_eq!(result, Ok(RunResult::Stopped));
+        assert_eq!(outputs, vec![2, 4, 6, 10]);
+        assert_eq!(vm.last_run_steps(), 5 + 3);
+    }
+
+    #[test]
+    fn iterators() {
+        let mut vm = VM::new(&DOUBLER);
+        let mut outputs = Vec::new();
+
+        // any iterator, here a range; once it's used up the VM waits for more
+        let mut input = from_iter(7..10);
+        let result = vm.run_with_io(&mut input, &mut |v| outputs.push(v));
+        assert_eq!(result, Ok(RunResult::NeedInput));
+        assert_eq!(outputs, vec![14, 16, 18]);
+        assert_eq!(input.next_input(), None);
+
+        let result = vm.run_with_io(&mut from_iter(Some(0)), &mut |v| outputs.push(v));
+        assert_eq!(result, Ok(RunResult::Stopped));
+        assert_eq!(outputs.len(), 3);
+    }
+
+    #[test]
+    fn channels() {
+        let (to_vm, vm_input) = channel();
+        let (vm_output, from_vm) = channel();
+
+        let handle = thread::spawn(move || {
+            let mut vm = VM::new(&DOUBLER);
+            vm.run_with_io(&mut from_channel(vm_input), &mut to_channel(vm_output))
+        });
+
+        // each answer comes back before the next question is asked
+        for i in 1..10 {
+            to_vm.send(i).unwrap();
+            assert_eq!(from_vm.recv(), Ok(2 * i));
+        }
+        to_vm.send(0).unwrap();
+
+        assert_eq!(handle.join().unwrap(), Ok(RunResult::Stopped));
+        assert!(from_vm.recv().is_err());
+    }
+
+    // Counts down to 0; finishing before it gets there is an error
+    struct Countdown(i64);
+
+    impl InputSource for Countdown {
+        fn next_input(&mut self) -> Option<i64> {
+            let val = self.0;
+            self.0 -= 1;
+            (val >= 0).then_some(val)
+        }
+
+        fn finish(&mut self) -> io::Result<()> {
+            match self.0 {
+                -1 => Ok(()),
+                _ => Err(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    "inputs left over",
+                )),
+            }
+        }
+    }
+
+    #[derive(Default)]
+    struct Sum {
+        total: i64,
+        count: usize,
+    }
+
+    impl OutputSink for Sum {
+        fn send(&mut self, val: i64) {
+            self.total += val;
+            self.count += 1;
+        }
+    }
+
+    #[test]
+    fn own_sources_and_sinks() {
+        let mut input = Countdown(4);
+        let mut output = Sum::default();
+
+        let mut vm = VM::new(&DOUBLER);
+        assert_eq!(
+            vm.run_with_io(&mut input, &mut output),
+            Ok(RunResult::Stopped)
+        );
+        assert!(input.finish().is_ok());
+        assert!(output.finish().is_ok());
+        assert_eq!((output.total, output.count), (2 * (4 + 3 + 2 + 1), 4));
+
+        // one that runs dry pauses the VM, which carries on once there's more
+        let mut vm = VM::new(&DOUBLER);
+        let mut input = Countdown(-1);
+        assert_eq!(
+            vm.run_with_io(&mut input, &mut output),
+            Ok(RunResult::NeedInput)
+        );
+        assert!(Countdown(2).finish().is_err());
+        assert_eq!(
+            vm.run_with_io(&mut Countdown(1), &mut output),
+            Ok(RunResult::Stopped)
+        );
+        assert_eq!((output.total, output.count), (22, 5));
+    }
+
+    #[test]
+    fn readers_and_writers() {
+        let mut input = ReaderSource::new("1, 2\n\n3 4,0\n".as_bytes());
+        let mut output = WriterSink::new(Vec::new());
+
+        let mut vm = VM::new(&DOUBLER);
+        assert_eq!(
+            vm.run_with_io(&mut input, &mut output),
+            Ok(RunResult::Stopped)
+        );
+        assert!(input.finish().is_ok());
+        assert!(output.finish().is_ok());
+        assert_eq!(String::from_utf8(output.out).unwrap(), "2\n4\n6\n8\n");
+
+        let mut input = ReaderSource::new("1\nx\n".as_bytes());
+        let mut vm = VM::new(&DOUBLER);
+        assert_eq!(
+            vm.run_with_io(&mut input, &mut |_| {}),
+            Ok(RunResult::NeedInput)
+        );
+        assert!(input.finish().is_err());
+    }
+}
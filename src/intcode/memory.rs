@@ -0,0 +1,240 @@
+use std::collections::HashMap;
+
+// Storage for a VM's memory. Every address is valid and reads as 0 until it's written,
+// and programs can (and do) write to huge addresses, so backends have to handle that
+// without allocating everything in between.
+pub trait Memory: Clone {
+    fn from_program(program: &[i64]) -> Self;
+
+    fn get(&self, address: usize) -> i64;
+
+    fn set(&mut self, address: usize, val: i64);
+
+    // The length of the program this was created from
+    fn program_len(&self) -> usize;
+
+    // Every nonzero word, sorted by address
+    fn nonzero_words(&self) -> Vec<(usize, i64)>;
+}
+
+// The program in a Vec, and everything beyond it in a HashMap. Simple, but every access
+// past the end of the program is a hash lookup.
+#[derive(Clone, Debug)]
+pub struct SparseMemory {
+    start: Vec<i64>,
+    map: HashMap<usize, i64>,
+}
+
+impl Memory for SparseMemory {
+    fn from_program(program: &[i64]) -> Self {
+        SparseMemory {
+            start: program.to_vec(),
+            map: HashMap::new(),
+        }
+    }
+
+    fn get(&self, address: usize) -> i64 {
+        match self.start.get(address) {
+            Some(&val) => val,
+            None => self.map.get(&address).copied().unwrap_or(0),
+        }
+    }
+
+    fn set(&mut self, address: usize, val: i64) {
+        match self.start.get_mut(address) {
+            Some(slot) => *slot = val,
+            None => {
+                self.map.insert(address, val);
+            }
+        }
+    }
+
+    fn program_len(&self) -> usize {
+        self.start.len()
+    }
+
+    fn nonzero_words(&self) -> Vec<(usize, i64)> {
+        let mut out = self
+            .start
+            .iter()
+            .copied()
+            .enumerate()
+            .chain(self.map.iter().map(|(&a, &v)| (a, v)))
+            .filter(|&(_, v)| v != 0)
+            .collect::<Vec<_>>();
+        out.sort();
+        out
+    }
+}
+
+// Addresses past this go in a HashMap, so one write to a huge address doesn't allocate
+// gigabytes; this covers the first 1M words (8MB), which is plenty for real programs
+const MAX_DENSE: usize = 1 << 20;
+const GROW_BY: usize = 64;
+
+// A Vec which grows to cover the highest address written, so most accesses are just an
+// index. Addresses too high for that fall back to a HashMap, like SparseMemory. This only
+// helps programs which use memory past their end, like day 9's; day 15 never does, so
+// there both backends do the same work, and dense is no faster.
+#[derive(Clone, Debug)]
+pub struct DenseMemory {
+    program_len: usize,
+    words: Vec<i64>,
+    far: HashMap<usize, i64>,
+}
+
+// Kept out of line, so the common case inlines into the VM without dragging these along
+impl DenseMemory {
+    #[cold]
+    #[inline(never)]
+    fn get_slow(&self, address: usize) -> i64 {
+        if address < MAX_DENSE {
+            return 0;
+        }
+        self.far.get(&address).copied().unwrap_or(0)
+    }
+
+    #[cold]
+    #[inline(never)]
+    fn set_slow(&mut self, address: usize, val: i64) {
+        if address < MAX_DENSE {
+            // grow in chunks; clones don't keep spare capacity, so growing a word at a time
+            // would mean a reallocation for the first write past the end in every clone
+            let new_len = ((address / GROW_BY) + 1) * GROW_BY;
+            self.words.resize(new_len.min(MAX_DENSE), 0);
+            self.words[address] = val;
+        } else {
+            self.far.insert(address, val);
+        }
+    }
+}
+
+impl Memory for DenseMemory {
+    fn from_program(program: &[i64]) -> Self {
+        DenseMemory {
+            program_len: program.len(),
+            words: program.to_vec(),
+            far: HashMap::new(),
+        }
+    }
+
+    #[inline]
+    fn get(&self, address: usize) -> i64 {
+        match self.words.get(address) {
+            Some(&val) => val,
+            None => self.get_slow(address),
+        }
+    }
+
+    #[inline]
+    fn set(&mut self, address: usize, val: i64) {
+        match self.words.get_mut(address) {
+            Some(slot) => *slot = val,
+            None => self.set_slow(address, val),
+        }
+    }
+
+    fn program_len(&self) -> usize {
+        self.program_len
+    }
+
+    fn nonzero_words(&self) -> Vec<(usize, i64)> {
+        let mut out = self
+            .words
+            .iter()
+            .copied()
+            .enumerate()
+            .filter(|&(_, v)| v != 0)
+            .collect::<Vec<_>>();
+
+        let mut far = self
+            .far
+            .iter()
+            .map(|(&a, &v)| (a, v))
+            .filter(|&(_, v)| v != 0)
+            .collect::<Vec<_>>();
+        far.sort();
+        out.extend(far);
+
+        out
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn addresses() -> Vec<usize> {
+        let mut out = vec![0, 1, 5, 1023, 1024, 3 * 1024 + 7];
+        out.push(MAX_DENSE - 1);
+        out.push(MAX_DENSE);
+        out.push(1 << 40);
+        out.push(usize::MAX);
+        out
+    }
+
+    fn check_backend<M: Memory>() {
+        let mut memory = M::from_program(&[1, 0, 3]);
+        assert_eq!(memory.program_len(), 3);
+        assert_eq!(memory.nonzero_words(), vec![(0, 1), (2, 3)]);
+
+        for (i, &address) in addresses().iter().enumerate() {
+            assert_eq!(
+                memory.get(address),
+                [1, 0, 3].get(address).copied().unwrap_or(0)
+            );
+            memory.set(address, i as i64 - 3);
+        }
+
+        let copy = memory.clone();
+        for (i, &address) in addresses().iter().enumerate() {
+            assert_eq!(memory.get(address), i as i64 - 3);
+            memory.set(address, 0);
+            assert_eq!(memory.get(address), 0);
+            assert_eq!(copy.get(address), i as i64 - 3);
+        }
+
+        assert_eq!(memory.program_len(), 3);
+        assert_eq!(memory.nonzero_words(), vec![(2, 3)]);
+
+        // everything written is nonzero except at 1023, plus 3 from the program
+        let nonzero = copy.nonzero_words();
+        assert_eq!(nonzero.len(), addresses().len());
+        assert_eq!(nonzero[2], (2, 3));
+        assert_eq!(nonzero[4], (1024, 1));
+        assert_eq!(nonzero.last(), Some(&(usize::MAX, 6)));
+    }
+
+    #[test]
+    fn sparse_memory() {
+        check_backend::<SparseMemory>();
+    }
+
+    #[test]
+    fn dense_memory() {
+        check_backend::<DenseMemory>();
+    }
+
+    #[test]
+    fn backends_agree() {
+        let program = (0..100).collect::<Vec<_>>();
+        let mut sparse = SparseMemory::from_program(&program);
+        let mut dense = DenseMemory::from_program(&program);
+
+        // a deterministic spread of low, medium and very high addresses
+        let mut x: u64 = 12345;
+        for i in 0..10_000 {
+            x = x
+                .wrapping_mul(6364136223846793005)
+                .wrapping_add(1442695040888963407);
+            let address = (x >> (x % 64)) as usize;
+            let val = if i % 5 == 0 { 0 } else { x as i64 };
+
+            sparse.set(address, val);
+            dense.set(address, val);
+            assert_eq!(sparse.get(address), dense.get(address));
+        }
+
+        assert_eq!(sparse.nonzero_words(), dense.nonzero_words());
+    }
+}
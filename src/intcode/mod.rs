@@ -0,0 +1,1083 @@
+use std::collections::{HashMap, VecDeque};
+use std::fmt;
+use std::ops::Range;
+use std::sync::{Arc, Mutex};
+
+mod activity;
+pub mod ascii;
+pub mod asm;
+pub mod debugger;
+pub mod disasm;
+pub mod graph;
+mod history;
+pub mod io;
+pub mod memory;
+pub mod network;
+pub mod patch;
+pub mod profile;
+mod snapshot;
+pub mod trace;
+
+use history::{History, UndoEntry};
+use memory::{DenseMemory, Memory};
+use patch::Patch;
+use profile::Profile;
+use trace::{TraceEvent, TraceSink};
+
+pub fn str_to_ints(s: &str) -> Vec<i64> {
+    s.trim()
+        .split(',')
+        .map(|token| token.trim().parse::<i64>().unwrap())
+        .collect()
+}
+
+// Generic over how memory is stored; see memory.rs. Most code can just use the default.
+#[derive(Clone)]
+pub struct VM<M: Memory = DenseMemory> {
+    code: M,
+    ip: usize,
+    relative_base: i64,
+    stopped: bool,
+    // available to use
+    stored_inputs: VecDeque<i64>,
+    // available to be polled
+    stored_outputs: VecDeque<i64>,
+    watchpoints: HashMap<usize, WatchKind>,
+    // set by the current instruction, if it touched a watched address
+    watch_hit: Option<WatchHit>,
+    // shared, so clones of a traced VM trace to the same place
+    tracer: Option<Arc<Mutex<dyn TraceSink + Send>>>,
+    // filled in by the current instruction, if tracing
+    trace_event: Option<TraceEvent>,
+    // None unless enabled; see history.rs
+    history: Option<History>,
+    // filled in by the current instruction, if keeping history
+    undo_entry: Option<UndoEntry>,
+    // None unless enabled; see profile.rs
+    profile: Option<Profile>,
+    // the decoded instruction at each address of the program, filled in as it runs;
+    // writes clear the entry they hit, so self-modifying code still decodes correctly
+    decoded: Vec<Option<Op>>,
+    total_steps: u64,
+    last_run_steps: u64,
+}
+
+impl VM {
+    pub fn new(code: &[i64]) -> Self {
+        VM::with_memory(DenseMemory::from_program(code))
+    }
+}
+
+impl<M: Memory> VM<M> {
+    pub fn with_memory(memory: M) -> Self {
+        VM {
+            decoded: vec![None; memory.program_len()],
+            code: memory,
+            ip: 0,
+            stopped: false,
+            relative_base: 0,
+            stored_inputs: VecDeque::new(),
+            stored_outputs: VecDeque::new(),
+            watchpoints: HashMap::new(),
+            watch_hit: None,
+            tracer: None,
+            trace_event: None,
+            history: None,
+            undo_entry: None,
+            profile: None,
+            total_steps: 0,
+            last_run_steps: 0,
+        }
+    }
+
+    // Applied before the program runs, e.g. to put in a cheat code
+    pub fn with_patch(mut self, patch: &Patch) -> Self {
+        for &(address, val) in patch.words() {
+            self.poke(address, val);
+        }
+        self
+    }
+
+    // Decodes every instruction afresh each time, as before there was a cache; for
+    // measuring what the cache is worth
+    #[cfg(test)]
+    pub(crate) fn without_decode_cache(mut self) -> Self {
+        self.decoded = Vec::new();
+        self
+    }
+
+    pub fn set_tracer(&mut self, tracer: Arc<Mutex<dyn TraceSink + Send>>) {
+        self.tracer = Some(tracer);
+    }
+
+    pub fn add_watchpoint(&mut self, address: usize, kind: WatchKind) {
+        self.watchpoints.insert(address, kind);
+    }
+
+    pub fn remove_watchpoint(&mut self, address: usize) -> bool {
+        self.watchpoints.remove(&address).is_some()
+    }
+
+    pub fn is_stopped(&self) -> bool {
+        self.stopped
+    }
+
+    pub fn ip(&self) -> usize {
+        self.ip
+    }
+
+    pub fn relative_base(&self) -> i64 {
+        self.relative_base
+    }
+
+    pub fn peek(&self, address: usize) -> i64 {
+        self.code.get(address)
+    }
+
+    pub fn peek_range(&self, range: Range<usize>) -> Vec<i64> {
+        range.map(|address| self.code.get(address)).collect()
+    }
+
+    // Writes from outside the program, so watchpoints, traces and history don't see them
+    pub fn poke(&mut self, address: usize, val: i64) {
+        self.set_memory(address, val);
+    }
+
+    // On an error, the VM is left exactly as it was before the faulting instruction,
+    // so the ip still points at it and running again will report the same error
+    pub fn run(&mut self) -> Result<RunResult, VmError> {
+        self.run_counted(None)
+    }
+
+    // Like run, but gives up with OutOfFuel after executing max_steps instructions. The VM
+    // is left just before the next instruction, so calling this (or run) again carries on.
+    pub fn run_with_limit(&mut self, max_steps: u64) -> Result<RunResult, VmError> {
+        self.run_counted(Some(max_steps))
+    }
+
+    fn run_counted(&mut self, max_steps: Option<u64>) -> Result<RunResult, VmError> {
+        let steps_before = self.total_steps;
+        let result = self.run_until_pause(max_steps);
+
+        self.last_run_steps = self.total_steps - steps_before;
+        result
+    }
+
+    fn run_until_pause(&mut self, max_steps: Option<u64>) -> Result<RunResult, VmError> {
+        let limit = max_steps.map(|max| self.total_steps.saturating_add(max));
+
+        loop {
+            if !self.stopped && limit.map_or(false, |limit| self.total_steps >= limit) {
+                return Ok(RunResult::OutOfFuel);
+            }
+
+            match self.step_inline()? {
+                StepResult::Ran => {}
+                StepResult::NeedInput => return Ok(RunResult::NeedInput),
+                StepResult::Stopped => return Ok(RunResult::Stopped),
+                StepResult::Watchpoint(hit) => return Ok(RunResult::Watchpoint(hit)),
+            }
+        }
+    }
+
+    // Executes (at most) one instruction
+    pub fn step(&mut self) -> Result<StepResult, VmError> {
+        self.step_inline()
+    }
+
+    // The run loop calls this directly, since step has so many callers that the compiler
+    // won't inline it there, and that makes the whole VM noticeably slower
+    #[inline(always)]
+    fn step_inline(&mut self) -> Result<StepResult, VmError> {
+        if self.stopped {
+            return Ok(StepResult::Stopped);
+        }
+
+        // println!("Code state is now {:?}", self.code);
+
+        self.watch_hit = None;
+
+        let op = self.decode(self.ip).map_err(|fault| self.fail(fault))?;
+
+        // println!("At ip {}, got op {:?}", self.ip, op);
+
+        if self.tracer.is_some() {
+            let params = (1..skip(op))
+                .map(|i| self.code.get(wrapping_add(self.ip, i)))
+                .collect();
+
+            self.trace_event = Some(TraceEvent {
+                ip: self.ip,
+                op,
+                params,
+                reads: Vec::new(),
+                writes: Vec::new(),
+                relative_base_change: None,
+                input: None,
+                output: None,
+            });
+        }
+
+        if self.history.is_some() {
+            self.undo_entry = Some(UndoEntry {
+                ip: self.ip,
+                relative_base: self.relative_base,
+                writes: Vec::new(),
+                input: None,
+                produced_output: false,
+            });
+        }
+
+        // jumps move the ip, so this is the only place to get the instruction's own address
+        let ip = self.ip;
+        let op_result = self.do_op(op);
+        // only finished instructions are traced or recorded. Moving these out is surprisingly
+        // expensive, so it's skipped unless something was actually filled in.
+        let trace_event = match self.trace_event {
+            Some(_) => self.trace_event.take(),
+            None => None,
+        };
+        let undo_entry = match self.undo_entry {
+            Some(_) => self.undo_entry.take(),
+            None => None,
+        };
+        let op_result = op_result.map_err(|fault| self.fail(fault))?;
+
+        match op_result {
+            OpResult::Success => {
+                if let (Some(entry), Some(history)) = (undo_entry, self.history.as_mut()) {
+                    history.push(entry);
+                }
+
+                if let (Some(event), Some(tracer)) = (trace_event, self.tracer.as_ref()) {
+                    if let Ok(mut tracer) = tracer.lock() {
+                        tracer.record(&event);
+                    }
+                }
+
+                self.ip = wrapping_add(self.ip, skip(op));
+                self.total_steps += 1;
+
+                if let Some(profile) = self.profile.as_mut() {
+                    profile.record_op(ip, op, self.ip);
+                }
+
+                match self.watch_hit.take() {
+                    Some(hit) => Ok(StepResult::Watchpoint(hit)),
+                    None => Ok(StepResult::Ran),
+                }
+            }
+            OpResult::NeedInput => Ok(StepResult::NeedInput),
+        }
+    }
+
+    fn fail(&self, fault: Fault) -> VmError {
+        let ip = self.ip;
+        let op_val = self.code.get(ip);
+        let relative_base = self.relative_base;
+
+        match fault {
+            Fault::UnknownOpcode => VmError::UnknownOpcode {
+                ip,
+                op_val,
+                relative_base,
+            },
+            Fault::UnknownMode(mode) => VmError::UnknownMode {
+                ip,
+                op_val,
+                relative_base,
+                mode,
+            },
+            Fault::NegativeAddress(address) => VmError::NegativeAddress {
+                ip,
+                op_val,
+                relative_base,
+                address,
+            },
+            Fault::ImmediateWrite => VmError::ImmediateWrite {
+                ip,
+                op_val,
+                relative_base,
+            },
+            Fault::Overflow => VmError::Overflow {
+                ip,
+                op_val,
+                relative_base,
+            },
+        }
+    }
+
+    pub fn give_input(&mut self, input: i64) {
+        self.stored_inputs.push_back(input);
+    }
+
+    pub fn get_next_output(&mut self) -> Option<i64> {
+        self.stored_outputs.pop_front()
+    }
+
+    pub fn get_all_outputs(&mut self) -> Vec<i64> {
+        let mut out = Vec::with_capacity(self.stored_outputs.len());
+        while let Some(val) = self.stored_outputs.pop_front() {
+            out.push(val);
+        }
+        out
+    }
+
+    // Every parameter read and write goes through these two, so watchpoints see them all
+    fn read(&mut self, address: usize) -> i64 {
+        let val = self.code.get(address);
+
+        if let Some(profile) = self.profile.as_mut() {
+            profile.record_read(address);
+        }
+
+        if self.watch_hit.is_none() && !self.watchpoints.is_empty() {
+            if let Some(kind) = self.watchpoints.get(&address) {
+                if kind.on_read() {
+                    self.watch_hit = Some(WatchHit {
+                        ip: self.ip,
+                        address,
+                        access: Access::Read,
+                        old_val: val,
+                        new_val: val,
+                    });
+                }
+            }
+        }
+
+        val
+    }
+
+    fn write(&mut self, address: usize, val: i64) {
+        if !self.watchpoints.is_empty() {
+            if let Some(kind) = self.watchpoints.get(&address) {
+                // a write is more interesting than a read, so it wins if an op does both
+                if kind.on_write() {
+                    self.watch_hit = Some(WatchHit {
+                        ip: self.ip,
+                        address,
+                        access: Access::Write,
+                        old_val: self.code.get(address),
+                        new_val: val,
+                    });
+                }
+            }
+        }
+
+        if let Some(event) = self.trace_event.as_mut() {
+            event.writes.push((address, val));
+        }
+
+        if let Some(entry) = self.undo_entry.as_mut() {
+            entry.writes.push((address, self.code.get(address)));
+        }
+
+        if let Some(profile) = self.profile.as_mut() {
+            profile.record_write(address);
+        }
+
+        self.set_memory(address, val);
+    }
+
+    // All memory changes go through here, to keep the decode cache honest
+    fn set_memory(&mut self, address: usize, val: i64) {
+        if let Some(op) = self.decoded.get_mut(address) {
+            *op = None;
+        }
+        self.code.set(address, val);
+    }
+
+    #[inline]
+    fn decode(&mut self, ip: usize) -> Result<Op, Fault> {
+        if let Some(&Some(op)) = self.decoded.get(ip) {
+            return Ok(op);
+        }
+
+        let op = to_op(self.code.get(ip))?;
+        if let Some(cached) = self.decoded.get_mut(ip) {
+            *cached = Some(op);
+        }
+        Ok(op)
+    }
+
+    fn get_val_from_memory(
+        &mut self,
+        mode: ParameterMode,
+        ip_with_offset: usize,
+    ) -> Result<i64, Fault> {
+        let val = match mode {
+            ParameterMode::Immediate => self.code.get(ip_with_offset),
+            ParameterMode::Position => {
+                let base_val = self.code.get(ip_with_offset);
+                let actual_ind = force_usize(base_val)?;
+                self.read(actual_ind)
+            }
+            ParameterMode::Relative => {
+                let base_val = checked(
+                    self.code
+                        .get(ip_with_offset)
+                        .checked_add(self.relative_base),
+                )?;
+                let actual_ind = force_usize(base_val)?;
+                self.read(actual_ind)
+            }
+        };
+
+        if let Some(event) = self.trace_event.as_mut() {
+            event.reads.push(val);
+        }
+
+        Ok(val)
+    }
+
+    fn set_val_in_memory(
+        &mut self,
+        mode: ParameterMode,
+        ip_with_offset: usize,
+        val: i64,
+    ) -> Result<(), Fault> {
+        match mode {
+            ParameterMode::Immediate => return Err(Fault::ImmediateWrite),
+            ParameterMode::Position => {
+                let dest = self.code.get(ip_with_offset);
+                let actual_ind = force_usize(dest)?;
+                self.write(actual_ind, val);
+            }
+            ParameterMode::Relative => {
+                let dest = checked(
+                    self.code
+                        .get(ip_with_offset)
+                        .checked_add(self.relative_base),
+                )?;
+                let actual_ind = force_usize(dest)?;
+                self.write(actual_ind, val);
+            }
+        }
+
+        Ok(())
+    }
+
+    fn do_op(&mut self, op: Op) -> Result<OpResult, Fault> {
+        let ip = self.ip;
+        match op {
+            Op::Add(mode_a, mode_b, mode_c) => {
+                let a = self.get_val_from_memory(mode_a, ip + 1)?;
+                let b = self.get_val_from_memory(mode_b, ip + 2)?;
+
+                self.set_val_in_memory(mode_c, ip + 3, checked(a.checked_add(b))?)?;
+            }
+            Op::Multiply(mode_a, mode_b, mode_c) => {
+                let a = self.get_val_from_memory(mode_a, ip + 1)?;
+                let b = self.get_val_from_memory(mode_b, ip + 2)?;
+
+                self.set_val_in_memory(mode_c, ip + 3, checked(a.checked_mul(b))?)?;
+            }
+            Op::TakeInput(mode) => {
+                let val = self.stored_inputs.front().copied();
+                if val.is_none() {
+                    return Ok(OpResult::NeedInput);
+                }
+                let val = val.unwrap();
+                // println!("Input: {}", val);
+
+                // only consume the input once the write succeeded, so a fault doesn't lose it
+                self.set_val_in_memory(mode, ip + 1, val)?;
+                self.stored_inputs.pop_front();
+
+                if let Some(event) = self.trace_event.as_mut() {
+                    event.input = Some(val);
+                }
+
+                if let Some(entry) = self.undo_entry.as_mut() {
+                    entry.input = Some(val);
+                }
+            }
+            Op::DoOutput(mode) => {
+                let val = self.get_val_from_memory(mode, ip + 1)?;
+                // println!("Output: {}", val);
+                self.stored_outputs.push_back(val);
+
+                if let Some(event) = self.trace_event.as_mut() {
+                    event.output = Some(val);
+                }
+
+                if let Some(entry) = self.undo_entry.as_mut() {
+                    entry.produced_output = true;
+                }
+            }
+            Op::JumpIfTrue(mode_a, mode_b) => {
+                let a = self.get_val_from_memory(mode_a, ip + 1)?;
+                if a != 0 {
+                    // NB: we subtract two from the val because we're going to add
+                    // it back at the end (it's a little janky but the alternative is to
+                    // copy paste a lot of "increment self.ip" code
+                    let b = self.get_val_from_memory(mode_b, ip + 2)?;
+                    self.ip = wrapping_sub(force_usize(b)?, 3);
+                }
+            }
+            Op::JumpIfFalse(mode_a, mode_b) => {
+                let a = self.get_val_from_memory(mode_a, ip + 1)?;
+                if a == 0 {
+                    // NB: we subtract two from the val because we're going to add
+                    // it back at the end (it's a little janky but the alternative is to
+                    // copy paste a lot of "increment self.ip" code
+                    let b = self.get_val_from_memory(mode_b, ip + 2)?;
+                    self.ip = wrapping_sub(force_usize(b)?, 3);
+                }
+            }
+            Op::LessThan(mode_a, mode_b, mode_c) => {
+                let a = self.get_val_from_memory(mode_a, ip + 1)?;
+                let b = self.get_val_from_memory(mode_b, ip + 2)?;
+
+                let val = if a < b { 1 } else { 0 };
+
+                self.set_val_in_memory(mode_c, ip + 3, val)?;
+            }
+            Op::Equals(mode_a, mode_b, mode_c) => {
+                let a = self.get_val_from_memory(mode_a, ip + 1)?;
+                let b = self.get_val_from_memory(mode_b, ip + 2)?;
+
+                let val = if a == b { 1 } else { 0 };
+
+                self.set_val_in_memory(mode_c, ip + 3, val)?;
+            }
+            Op::AdjustRelBase(mode) => {
+                let rb_adj = self.get_val_from_memory(mode, ip + 1)?;
+                let old = self.relative_base;
+                self.relative_base = checked(old.checked_add(rb_adj))?;
+
+                if let Some(event) = self.trace_event.as_mut() {
+                    event.relative_base_change = Some((old, self.relative_base));
+                }
+            }
+            Op::Stop => {
+                self.stopped = true;
+            }
+        }
+
+        Ok(OpResult::Success)
+    }
+}
+
+#[derive(Debug, Eq, PartialEq, Copy, Clone)]
+pub enum RunResult {
+    Stopped,
+    NeedInput,
+    // The instruction that touched the address has finished, so the VM can just be resumed
+    Watchpoint(WatchHit),
+    // Only from run_with_limit; the VM can be resumed
+    OutOfFuel,
+}
+
+#[derive(Debug, Eq, PartialEq, Copy, Clone, Hash)]
+pub enum WatchKind {
+    Read,
+    Write,
+    ReadWrite,
+}
+
+impl WatchKind {
+    fn on_read(self) -> bool {
+        self != WatchKind::Write
+    }
+
+    fn on_write(self) -> bool {
+        self != WatchKind::Read
+    }
+}
+
+#[derive(Debug, Eq, PartialEq, Copy, Clone, Hash)]
+pub enum Access {
+    Read,
+    Write,
+}
+
+#[derive(Debug, Eq, PartialEq, Copy, Clone)]
+pub struct WatchHit {
+    // of the instruction that did the access
+    pub ip: usize,
+    pub address: usize,
+    pub access: Access,
+    // for reads, these are the same
+    pub old_val: i64,
+    pub new_val: i64,
+}
+
+#[derive(Debug, Eq, PartialEq, Copy, Clone)]
+pub enum StepResult {
+    // Executed an instruction; note that executing HLT still counts
+    Ran,
+    NeedInput,
+    Stopped,
+    Watchpoint(WatchHit),
+}
+
+#[derive(Debug, Eq, PartialEq, Copy, Clone)]
+pub enum VmError {
+    UnknownOpcode {
+        ip: usize,
+        op_val: i64,
+        relative_base: i64,
+    },
+    UnknownMode {
+        ip: usize,
+        op_val: i64,
+        relative_base: i64,
+        mode: i64,
+    },
+    NegativeAddress {
+        ip: usize,
+        op_val: i64,
+        relative_base: i64,
+        address: i64,
+    },
+    ImmediateWrite {
+        ip: usize,
+        op_val: i64,
+        relative_base: i64,
+    },
+    // a result, or a relative address, too big for an i64
+    Overflow {
+        ip: usize,
+        op_val: i64,
+        relative_base: i64,
+    },
+}
+
+impl VmError {
+    pub fn ip(&self) -> usize {
+        match *self {
+            VmError::UnknownOpcode { ip, .. } => ip,
+            VmError::UnknownMode { ip, .. } => ip,
+            VmError::NegativeAddress { ip, .. } => ip,
+            VmError::ImmediateWrite { ip, .. } => ip,
+            VmError::Overflow { ip, .. } => ip,
+        }
+    }
+
+    pub fn op_val(&self) -> i64 {
+        match *self {
+            VmError::UnknownOpcode { op_val, .. } => op_val,
+            VmError::UnknownMode { op_val, .. } => op_val,
+            VmError::NegativeAddress { op_val, .. } => op_val,
+            VmError::ImmediateWrite { op_val, .. } => op_val,
+            VmError::Overflow { op_val, .. } => op_val,
+        }
+    }
+
+    pub fn relative_base(&self) -> i64 {
+        match *self {
+            VmError::UnknownOpcode { relative_base, .. } => relative_base,
+            VmError::UnknownMode { relative_base, .. } => relative_base,
+            VmError::NegativeAddress { relative_base, .. } => relative_base,
+            VmError::ImmediateWrite { relative_base, .. } => relative_base,
+            VmError::Overflow { relative_base, .. } => relative_base,
+        }
+    }
+
+    pub fn description(&self) -> String {
+        match *self {
+            VmError::UnknownOpcode { op_val, .. } => {
+                format!("Unrecognized op code {}", op_val)
+            }
+            VmError::UnknownMode { mode, .. } => format!("Unrecognized parameter mode {}", mode),
+            VmError::NegativeAddress { address, .. } => {
+                format!("Cannot use negative value {} as an address", address)
+            }
+            VmError::ImmediateWrite { .. } => {
+                "Cannot set val where the index is in immediate mode".to_string()
+            }
+            VmError::Overflow { .. } => "Arithmetic overflowed a 64-bit value".to_string(),
+        }
+    }
+}
+
+impl fmt::Display for VmError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{} (ip {}, op code {}, relative base {})",
+            self.description(),
+            self.ip(),
+            self.op_val(),
+            self.relative_base()
+        )
+    }
+}
+
+impl std::error::Error for VmError {}
+
+// What went wrong, without the VM state; the VM fills that in (see VM::fail)
+#[derive(Debug, Eq, PartialEq, Copy, Clone)]
+enum Fault {
+    UnknownOpcode,
+    UnknownMode(i64),
+    NegativeAddress(i64),
+    ImmediateWrite,
+    Overflow,
+}
+
+fn checked(val: Option<i64>) -> Result<i64, Fault> {
+    val.ok_or(Fault::Overflow)
+}
+
+enum OpResult {
+    NeedInput,
+    Success,
+}
+
+fn wrapping_add(a: usize, b: usize) -> usize {
+    let out = std::num::Wrapping(a) + std::num::Wrapping(b);
+    out.0
+}
+
+fn wrapping_sub(a: usize, b: usize) -> usize {
+    let out = std::num::Wrapping(a) - std::num::Wrapping(b);
+    out.0
+}
+
+fn force_usize(val: i64) -> Result<usize, Fault> {
+    if val < 0 {
+        return Err(Fault::NegativeAddress(val));
+    }
+    Ok(val as usize)
+}
+
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug)]
+pub enum ParameterMode {
+    Position,
+    Immediate,
+    Relative,
+}
+
+fn to_mode(mode: usize) -> Result<ParameterMode, Fault> {
+    match mode {
+        0 => Ok(ParameterMode::Position),
+        1 => Ok(ParameterMode::Immediate),
+        2 => Ok(ParameterMode::Relative),
+        _ => Err(Fault::UnknownMode(mode as i64)),
+    }
+}
+
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug)]
+pub enum Op {
+    // Mode for param ip+1, param ip+2, param ip+3
+    Add(ParameterMode, ParameterMode, ParameterMode),
+    // mm for rest
+    Multiply(ParameterMode, ParameterMode, ParameterMode),
+    TakeInput(ParameterMode),
+    DoOutput(ParameterMode),
+    JumpIfTrue(ParameterMode, ParameterMode),
+    JumpIfFalse(ParameterMode, ParameterMode),
+    LessThan(ParameterMode, ParameterMode, ParameterMode),
+    Equals(ParameterMode, ParameterMode, ParameterMode),
+    AdjustRelBase(ParameterMode),
+    Stop,
+}
+
+fn next_mode(op_val: &mut usize) -> Result<ParameterMode, Fault> {
+    let rem = *op_val % 10;
+    *op_val /= 10;
+    to_mode(rem)
+}
+
+fn to_op(op_val: i64) -> Result<Op, Fault> {
+    use Op::*;
+
+    if op_val < 0 {
+        return Err(Fault::UnknownOpcode);
+    }
+
+    let mut op_val = op_val as usize;
+
+    let simple_op = op_val % 100;
+    op_val /= 100;
+
+    match simple_op {
+        1 => {
+            let mode_a = next_mode(&mut op_val)?;
+            let mode_b = next_mode(&mut op_val)?;
+            let mode_c = next_mode(&mut op_val)?;
+
+            Ok(Add(mode_a, mode_b, mode_c))
+        }
+        2 => {
+            let mode_a = next_mode(&mut op_val)?;
+            let mode_b = next_mode(&mut op_val)?;
+            let mode_c = next_mode(&mut op_val)?;
+
+            Ok(Multiply(mode_a, mode_b, mode_c))
+        }
+        3 => {
+            let mode = next_mode(&mut op_val)?;
+            Ok(TakeInput(mode))
+        }
+        4 => {
+            let mode = next_mode(&mut op_val)?;
+            Ok(DoOutput(mode))
+        }
+        5 => {
+            let mode_a = next_mode(&mut op_val)?;
+            let mode_b = next_mode(&mut op_val)?;
+
+            Ok(JumpIfTrue(mode_a, mode_b))
+        }
+        6 => {
+            let mode_a = next_mode(&mut op_val)?;
+            let mode_b = next_mode(&mut op_val)?;
+
+            Ok(JumpIfFalse(mode_a, mode_b))
+        }
+        7 => {
+            let mode_a = next_mode(&mut op_val)?;
+            let mode_b = next_mode(&mut op_val)?;
+            let mode_c = next_mode(&mut op_val)?;
+
+            Ok(LessThan(mode_a, mode_b, mode_c))
+        }
+        8 => {
+            let mode_a = next_mode(&mut op_val)?;
+            let mode_b = next_mode(&mut op_val)?;
+            let mode_c = next_mode(&mut op_val)?;
+
+            Ok(Equals(mode_a, mode_b, mode_c))
+        }
+        9 => {
+            let mode_a = next_mode(&mut op_val)?;
+
+            Ok(AdjustRelBase(mode_a))
+        }
+        99 => Ok(Stop),
+        _ => Err(Fault::UnknownOpcode),
+    }
+}
+
+fn skip(op: Op) -> usize {
+    use Op::*;
+
+    match op {
+        Add(_, _, _) => 4,
+        Multiply(_, _, _) => 4,
+        TakeInput(_) => 2,
+        DoOutput(_) => 2,
+        JumpIfTrue(_, _) => 3,
+        JumpIfFalse(_, _) => 3,
+        LessThan(_, _, _) => 4,
+        Equals(_, _, _) => 4,
+        AdjustRelBase(_) => 2,
+        Stop => 1,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn unknown_opcode_is_an_error() {
+        let mut vm = VM::new(&[1101, 2, 3, 5, 42, 0]);
+
+        let err = vm.run().unwrap_err();
+        assert_eq!(
+            err,
+            VmError::UnknownOpcode {
+                ip: 4,
+                op_val: 42,
+                relative_base: 0
+            }
+        );
+
+        // the VM doesn't move past the fault
+        assert!(!vm.is_stopped());
+        assert_eq!(vm.run(), Err(err));
+    }
+
+    #[test]
+    fn bad_mode_is_an_error() {
+        let mut vm = VM::new(&[109, 3, 304, 0, 99]);
+
+        let err = vm.run().unwrap_err();
+        assert_eq!(
+            err,
+            VmError::UnknownMode {
+                ip: 2,
+                op_val: 304,
+                relative_base: 3,
+                mode: 3
+            }
+        );
+    }
+
+    #[test]
+    fn negative_address_is_an_error() {
+        let mut vm = VM::new(&[4, -7, 99]);
+
+        let err = vm.run().unwrap_err();
+        assert_eq!(err.ip(), 0);
+        assert_eq!(err.op_val(), 4);
+        assert_eq!(
+            err,
+            VmError::NegativeAddress {
+                ip: 0,
+                op_val: 4,
+                relative_base: 0,
+                address: -7
+            }
+        );
+    }
+
+    #[test]
+    fn immediate_write_keeps_input() {
+        let mut vm = VM::new(&[103, 0, 99]);
+        vm.give_input(17);
+
+        let err = vm.run().unwrap_err();
+        assert_eq!(
+            err,
+            VmError::ImmediateWrite {
+                ip: 0,
+                op_val: 103,
+                relative_base: 0
+            }
+        );
+        assert_eq!(vm.stored_inputs.front(), Some(&17));
+    }
+
+    #[test]
+    fn overflow_is_an_error() {
+        let overflow = |ip, op_val, relative_base| VmError::Overflow {
+            ip,
+            op_val,
+            relative_base,
+        };
+
+        let mut vm = VM::new(&[1101, i64::MAX, 1, 0, 99]);
+        assert_eq!(vm.run(), Err(overflow(0, 1101, 0)));
+        // nothing was written
+        assert_eq!(vm.peek(0), 1101);
+
+        let mut vm = VM::new(&[1102, i64::MIN, -1, 0, 99]);
+        assert_eq!(vm.run(), Err(overflow(0, 1102, 0)));
+
+        // the relative base itself, then an address relative to it
+        let mut vm = VM::new(&[109, i64::MAX, 109, 1, 99]);
+        assert_eq!(vm.run(), Err(overflow(2, 109, i64::MAX)));
+        let mut vm = VM::new(&[109, i64::MAX, 204, 1, 99]);
+        assert_eq!(vm.run(), Err(overflow(2, 204, i64::MAX)));
+    }
+
+    #[test]
+    fn watchpoints_pause_and_resume() {
+        // x = in; y = x + x; y *= 3; out y
+        let mut vm = VM::new(&[3, 13, 1, 13, 13, 14, 1002, 14, 3, 14, 4, 14, 99, 0, 0]);
+        vm.add_watchpoint(13, WatchKind::Read);
+        vm.give_input(5);
+
+        let hit = WatchHit {
+            ip: 2,
+            address: 13,
+            access: Access::Read,
+            old_val: 5,
+            new_val: 5,
+        };
+        assert_eq!(vm.run(), Ok(RunResult::Watchpoint(hit)));
+        assert_eq!(vm.ip(), 6);
+
+        // the multiply reads 14 and then writes it; the write is reported
+        vm.add_watchpoint(14, WatchKind::ReadWrite);
+        let hit = WatchHit {
+            ip: 6,
+            address: 14,
+            access: Access::Write,
+            old_val: 10,
+            new_val: 30,
+        };
+        assert_eq!(vm.run(), Ok(RunResult::Watchpoint(hit)));
+
+        assert!(vm.remove_watchpoint(13));
+        assert!(vm.remove_watchpoint(14));
+        assert!(!vm.remove_watchpoint(14));
+        assert_eq!(vm.run(), Ok(RunResult::Stopped));
+        assert_eq!(vm.get_all_outputs(), vec![30]);
+    }
+
+    #[test]
+    fn fuel_limit_stops_infinite_loop() {
+        // JT #1, #0
+        let mut vm = VM::new(&[1105, 1, 0]);
+
+        assert_eq!(vm.run_with_limit(10), Ok(RunResult::OutOfFuel));
+        assert_eq!(vm.last_run_steps(), 10);
+        assert_eq!(vm.ip(), 0);
+        assert!(!vm.is_stopped());
+
+        assert_eq!(vm.run_with_limit(0), Ok(RunResult::OutOfFuel));
+        assert_eq!(vm.total_steps(), 10);
+    }
+
+    #[test]
+    fn fuel_limit_is_resumable() {
+        let code = str_to_ints(include_str!("../resources/9a.txt"));
+
+        let mut expected = VM::new(&code);
+        expected.give_input(1);
+        assert_eq!(expected.run(), Ok(RunResult::Stopped));
+
+        let mut vm = VM::new(&code);
+        vm.give_input(1);
+        let mut runs = 0;
+        while vm.run_with_limit(7).unwrap() == RunResult::OutOfFuel {
+            runs += 1;
+        }
+
+        assert_eq!(runs, expected.last_run_steps() as usize / 7);
+        assert_eq!(vm.total_steps(), expected.total_steps());
+        assert_eq!(vm.get_all_outputs(), expected.get_all_outputs());
+
+        // running out of fuel doesn't hide the other results
+        assert_eq!(vm.run_with_limit(0), Ok(RunResult::Stopped));
+    }
+
+    #[test]
+    fn self_modifying_code() {
+        // the second time round the loop, the ADD at op has become a MUL
+        let code = asm::assemble(
+            "
+            loop:  OUT [n]
+            op:    ADD [n], #3, [n]
+                   ADD [op], #1, [op]
+                   ADD [count], #-1, [count]
+                   JT [count], #loop
+                   OUT [n]
+                   HLT
+            n:     DATA 1
+            count: DATA 2
+            ",
+        )
+        .unwrap();
+
+        let mut vm = VM::new(&code);
+        assert_eq!(vm.run(), Ok(RunResult::Stopped));
+        assert_eq!(vm.get_all_outputs(), vec![1, 4, 12]);
+    }
+
+    #[test]
+    fn step_back_invalidates_decoded_ops() {
+        // OUT #7; make that OUT [7] instead; jump back to it
+        let code = [104, 7, 1101, 4, 0, 0, 1105, 1, 0];
+
+        let mut vm = VM::new(&code);
+        vm.enable_history();
+        for _ in 0..4 {
+            vm.step().unwrap();
+        }
+        assert_eq!(vm.get_all_outputs(), vec![7, 1]);
+
+        assert!(vm.run_back_to(0));
+        assert!(vm.run_back_to(0));
+        assert_eq!(vm.peek(0), 104);
+
+        vm.step().unwrap();
+        assert_eq!(vm.get_all_outputs(), vec![7]);
+    }
+}
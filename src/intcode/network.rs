@@ -0,0 +1,752 @@
+use std::cell::Cell;
+use std::collections::VecDeque;
+use std::fmt;
+use std::io::{self, Write};
+use std::sync::mpsc::{channel, Receiver, RecvTimeoutError, Sender, TryRecvError};
+use std::thread;
+
+use super::activity::{Activity, QUIET_CHECK};
+use super::memory::{DenseMemory, Memory};
+use super::{RunResult, VmError, VM};
+
+// Every VM on the network is a node, whose address is its index. Nodes send packets by
+// outputting (destination, x, y), and receive them by reading x then y.
+
+#[derive(Debug, Eq, PartialEq, Copy, Clone, Hash)]
+pub struct Packet {
+    pub dest: usize,
+    pub x: i64,
+    pub y: i64,
+}
+
+// What a node reads when it asks for input and has no packets waiting
+#[derive(Debug, Eq, PartialEq, Copy, Clone)]
+pub enum EmptyQueue {
+    Feed(i64),
+    // Don't give it anything until a packet arrives
+    Wait,
+}
+
+#[derive(Debug, Eq, PartialEq, Copy, Clone)]
+pub enum Control {
+    Continue,
+    Stop,
+}
+
+// Watches a special address, which isn't a node
+pub trait Hook {
+    // Sees every packet sent to the hook's address
+    fn receive(&mut self, packet: Packet) -> Control;
+
+    // Called when the whole network is idle; a packet returned here is delivered, to wake
+    // it up again, and otherwise the network stops
+    fn idle(&mut self) -> Option<Packet> {
+        None
+    }
+}
+
+// For networks with no hook; with no hook address set, it never sees anything
+impl Hook for () {
+    fn receive(&mut self, _packet: Packet) -> Control {
+        Control::Continue
+    }
+}
+
+// Writes out what's sent to the hook address and when the network goes idle, and
+// optionally acts as day 23's NAT: when idle, it resends the last packet to node 0, until
+// it would send the same y twice in a row
+pub struct NetworkPrinter<W: Write> {
+    out: W,
+    nat: bool,
+    last: Option<Packet>,
+    last_resent_y: Option<i64>,
+    error: Option<io::Error>,
+}
+
+impl<W: Write> NetworkPrinter<W> {
+    pub fn new(out: W, nat: bool) -> Self {
+        NetworkPrinter {
+            out,
+            nat,
+            last: None,
+            last_resent_y: None,
+            error: None,
+        }
+    }
+
+    fn print(&mut self, line: fmt::Arguments) {
+        if self.error.is_none() {
+            if let Err(e) = writeln!(self.out, "{}", line) {
+                self.error = Some(e);
+            }
+        }
+    }
+
+    // Reports the first error writing out, since the hook has nowhere to send it
+    pub fn finish(&mut self) -> io::Result<()> {
+        if let Some(e) = self.error.take() {
+            return Err(e);
+        }
+        self.out.flush()
+    }
+}
+
+impl<W: Write> Hook for NetworkPrinter<W> {
+    fn receive(&mut self, packet: Packet) -> Control {
+        self.print(format_args!(
+            "Packet to {}: x {}, y {}",
+            packet.dest, packet.x, packet.y
+        ));
+        self.last = Some(packet);
+        Control::Continue
+    }
+
+    fn idle(&mut self) -> Option<Packet> {
+        self.print(format_args!("Network idle"));
+        if !self.nat {
+            return None;
+        }
+
+        let last = self.last?;
+        if self.last_resent_y == Some(last.y) {
+            self.print(format_args!("Resent y {} twice in a row", last.y));
+            return None;
+        }
+        self.last_resent_y = Some(last.y);
+        Some(Packet { dest: 0, ..last })
+    }
+}
+
+#[derive(Debug, Eq, PartialEq, Copy, Clone)]
+pub enum NetworkResult {
+    // The hook asked to stop
+    Stopped,
+    // Every node is idle, and the hook didn't wake any of them
+    Idle,
+    // Every node halted
+    Halted,
+}
+
+#[derive(Debug, Eq, PartialEq, Copy, Clone)]
+pub enum NetworkError {
+    Fault { address: usize, error: VmError },
+    UnknownAddress(Packet),
+}
+
+impl fmt::Display for NetworkError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            NetworkError::Fault { address, error } => {
+                write!(f, "Node {} faulted: {}", address, error)
+            }
+            NetworkError::UnknownAddress(packet) => write!(
+                f,
+                "Packet ({}, {}) sent to unknown address {}",
+                packet.x, packet.y, packet.dest
+            ),
+        }
+    }
+}
+
+impl std::error::Error for NetworkError {}
+
+// Collects a node's outputs into packets; a node may output a packet over several runs
+struct PacketBuilder {
+    words: Vec<i64>,
+}
+
+impl PacketBuilder {
+    fn new() -> Self {
+        PacketBuilder { words: Vec::new() }
+    }
+
+    fn push(&mut self, val: i64) -> Option<Result<Packet, NetworkError>> {
+        self.words.push(val);
+        if self.words.len() < 3 {
+            return None;
+        }
+
+        let (dest, x, y) = (self.words[0], self.words[1], self.words[2]);
+        self.words.clear();
+
+        let packet = Packet {
+            dest: dest as usize,
+            x,
+            y,
+        };
+        if dest < 0 {
+            return Some(Err(NetworkError::UnknownAddress(packet)));
+        }
+        Some(Ok(packet))
+    }
+}
+
+struct Node<M: Memory> {
+    vm: VM<M>,
+    queue: VecDeque<Packet>,
+    packets: PacketBuilder,
+    // paused for input, so it's safe to give it some
+    waiting: bool,
+    halted: bool,
+}
+
+pub struct Network<M: Memory = DenseMemory> {
+    nodes: Vec<Node<M>>,
+    empty_queue: EmptyQueue,
+    hook_address: Option<usize>,
+}
+
+impl Network {
+    // size copies of the program, each given its address as its first input
+    pub fn new(code: &[i64], size: usize) -> Self {
+        let vms = (0..size)
+            .map(|address| {
+                let mut vm = VM::new(code);
+                vm.give_input(address as i64);
+                vm
+            })
+            .collect();
+        Network::from_vms(vms)
+    }
+}
+
+impl<M: Memory> Network<M> {
+    // The VMs' addresses are their indices; by default empty queues feed -1
+    pub fn from_vms(vms: Vec<VM<M>>) -> Self {
+        let nodes = vms
+            .into_iter()
+            .map(|vm| Node {
+                vm,
+                queue: VecDeque::new(),
+                packets: PacketBuilder::new(),
+                waiting: false,
+                halted: false,
+            })
+            .collect();
+
+        Network {
+            nodes,
+            empty_queue: EmptyQueue::Feed(-1),
+            hook_address: None,
+        }
+    }
+
+    pub fn with_empty_queue(mut self, empty_queue: EmptyQueue) -> Self {
+        self.empty_queue = empty_queue;
+        self
+    }
+
+    pub fn with_hook_address(mut self, address: usize) -> Self {
+        self.hook_address = Some(address);
+        self
+    }
+
+    // Delivers a packet, or hands it to the hook
+    fn route(&mut self, packet: Packet, hook: &mut dyn Hook) -> Result<Control, NetworkError> {
+        if Some(packet.dest) == self.hook_address {
+            return Ok(hook.receive(packet));
+        }
+
+        match self.nodes.get_mut(packet.dest) {
+            // nobody will ever read it
+            Some(node) if node.halted => {}
+            Some(node) => node.queue.push_back(packet),
+            None => return Err(NetworkError::UnknownAddress(packet)),
+        }
+        Ok(Control::Continue)
+    }
+
+    // Runs each node in turn, deterministically, until the hook says to stop, the network
+    // goes idle, or every node halts. The network is idle after a round in which every
+    // node asked for input with nothing waiting, and nobody sent anything.
+    pub fn run(&mut self, hook: &mut dyn Hook) -> Result<NetworkResult, NetworkError> {
+        loop {
+            let mut idle = true;
+
+            for address in 0..self.nodes.len() {
+                let empty_queue = self.empty_queue;
+                let node = &mut self.nodes[address];
+                if node.halted {
+                    continue;
+                }
+
+                if node.waiting {
+                    match (node.queue.pop_front(), empty_queue) {
+                        (Some(packet), _) => {
+                            node.vm.give_input(packet.x);
+                            node.vm.give_input(packet.y);
+                            idle = false;
+                        }
+                        (None, EmptyQueue::Feed(val)) => node.vm.give_input(val),
+                        (None, EmptyQueue::Wait) => continue,
+                    }
+                } else {
+                    idle = false;
+                }
+
+                let result = node
+                    .vm
+                    .run()
+                    .map_err(|error| NetworkError::Fault { address, error })?;
+                node.waiting = result == RunResult::NeedInput;
+                node.halted = result == RunResult::Stopped;
+
+                let mut sent = Vec::new();
+                for val in node.vm.get_all_outputs() {
+                    if let Some(packet) = node.packets.push(val) {
+                        sent.push(packet?);
+                    }
+                }
+
+                for packet in sent {
+                    idle = false;
+                    if self.route(packet, hook)? == Control::Stop {
+                        return Ok(NetworkResult::Stopped);
+                    }
+                }
+            }
+
+            if self.nodes.iter().all(|node| node.halted) {
+                return Ok(NetworkResult::Halted);
+            }
+
+            if idle && self.nodes.iter().all(|node| node.queue.is_empty()) {
+                match hook.idle() {
+                    Some(packet) => {
+                        if self.route(packet, hook)? == Control::Stop {
+                            return Ok(NetworkResult::Stopped);
+                        }
+                    }
+                    None => return Ok(NetworkResult::Idle),
+                }
+            }
+        }
+    }
+}
+
+enum Message {
+    Packet(Packet),
+    BadPacket(NetworkError),
+    Done(usize, Result<RunResult, VmError>),
+}
+
+impl<M: Memory + Send> Network<M> {
+    // Runs each node on its own thread, with the router (and so the hook) on this one.
+    // Stops for the same reasons as run, but since the nodes run at their own pace the
+    // order of packets isn't deterministic. Packets nobody read are queued again after.
+    pub fn run_threaded(&mut self, hook: &mut dyn Hook) -> Result<NetworkResult, NetworkError> {
+        let size = self.nodes.len();
+        // the router is the last slot, always waiting; what's queued for it is packets
+        // nodes have sent that it hasn't routed yet
+        let router_slot = size;
+        let activity = Activity::new(size + 1);
+        activity.set_waiting(router_slot, true);
+
+        let empty_queue = self.empty_queue;
+        let hook_address = self.hook_address;
+        let (router, messages) = channel();
+
+        let (result, receivers) = thread::scope(|scope| {
+            let mut senders = Vec::with_capacity(size);
+            let mut handles = Vec::with_capacity(size);
+            for (address, node) in self.nodes.iter_mut().enumerate() {
+                let (sender, receiver) = channel();
+                for packet in node.queue.drain(..) {
+                    activity.sent(address);
+                    sender.send(packet).unwrap();
+                }
+                senders.push(Some(sender));
+
+                let activity = &activity;
+                let router = router.clone();
+                handles.push(scope.spawn(move || {
+                    run_node(
+                        address,
+                        node,
+                        receiver,
+                        empty_queue,
+                        activity,
+                        router,
+                        router_slot,
+                    )
+                }));
+            }
+            drop(router);
+
+            let send = |packet: Packet, senders: &[Option<Sender<Packet>>]| {
+                match senders.get(packet.dest) {
+                    Some(Some(sender)) => {
+                        activity.sent(packet.dest);
+                        sender.send(packet).unwrap();
+                        Ok(())
+                    }
+                    // halted
+                    Some(None) => Ok(()),
+                    None => Err(NetworkError::UnknownAddress(packet)),
+                }
+            };
+
+            let mut running = size;
+            let result = loop {
+                if running == 0 {
+                    break Ok(NetworkResult::Halted);
+                }
+
+                let packet = match messages.recv_timeout(QUIET_CHECK) {
+                    Ok(Message::Packet(packet)) => {
+                        activity.received(router_slot);
+                        packet
+                    }
+                    Ok(Message::BadPacket(e)) => break Err(e),
+                    Ok(Message::Done(address, result)) => {
+                        if let Err(error) = result {
+                            break Err(NetworkError::Fault { address, error });
+                        }
+                        senders[address] = None;
+                        running -= 1;
+                        continue;
+                    }
+                    Err(RecvTimeoutError::Timeout) => {
+                        if !activity.quiet() {
+                            continue;
+                        }
+                        match hook.idle() {
+                            Some(packet) => packet,
+                            None => break Ok(NetworkResult::Idle),
+                        }
+                    }
+                    Err(RecvTimeoutError::Disconnected) => break Ok(NetworkResult::Halted),
+                };
+
+                if Some(packet.dest) == hook_address {
+                    if hook.receive(packet) == Control::Stop {
+                        break Ok(NetworkResult::Stopped);
+                    }
+                } else if let Err(e) = send(packet, &senders) {
+                    break Err(e);
+                }
+            };
+
+            // dropping the senders ends the nodes, once they next ask for input
+            drop(senders);
+            drop(messages);
+            let receivers: Vec<_> = handles
+                .into_iter()
+                .map(|handle| handle.join().unwrap())
+                .collect();
+            (result, receivers)
+        });
+
+        for (node, receiver) in self.nodes.iter_mut().zip(receivers) {
+            if !node.halted {
+                node.queue.extend(receiver.try_iter());
+            }
+        }
+        result
+    }
+}
+
+// Runs a node until it halts or the router stops it, and hands back its receiver
+fn run_node<M: Memory>(
+    address: usize,
+    node: &mut Node<M>,
+    receiver: Receiver<Packet>,
+    empty_queue: EmptyQueue,
+    activity: &Activity,
+    router: Sender<Message>,
+    router_slot: usize,
+) -> Receiver<Packet> {
+    let mut y = None;
+    let mut stopped = false;
+    // whether the last thing it read was the empty queue value, with nothing sent since
+    let fed = Cell::new(false);
+
+    let take = |packet: Packet, y: &mut Option<i64>| {
+        activity.set_waiting(address, false);
+        activity.received(address);
+        fed.set(false);
+        *y = Some(packet.y);
+        packet.x
+    };
+
+    let mut input = || {
+        if let Some(y) = y.take() {
+            return Some(y);
+        }
+
+        match receiver.try_recv() {
+            Ok(packet) => return Some(take(packet, &mut y)),
+            Err(TryRecvError::Disconnected) => {
+                stopped = true;
+                return None;
+            }
+            Err(TryRecvError::Empty) => {}
+        }
+
+        match empty_queue {
+            EmptyQueue::Feed(val) => {
+                // idle once it's back for more after being fed, with nothing on its way
+                if fed.get() && activity.queued(address) == 0 {
+                    activity.set_waiting(address, true);
+                }
+                fed.set(true);
+                // the router can't see disconnection from here, so the next read will
+                thread::yield_now();
+                Some(val)
+            }
+            EmptyQueue::Wait => {
+                activity.set_waiting(address, true);
+                match receiver.recv() {
+                    Ok(packet) => Some(take(packet, &mut y)),
+                    Err(_) => {
+                        stopped = true;
+                        None
+                    }
+                }
+            }
+        }
+    };
+
+    let packets = &mut node.packets;
+    let mut output = |val| {
+        if let Some(packet) = packets.push(val) {
+            activity.set_waiting(address, false);
+            fed.set(false);
+            let message = match packet {
+                Ok(packet) => {
+                    activity.sent(router_slot);
+                    Message::Packet(packet)
+                }
+                Err(e) => Message::BadPacket(e),
+            };
+            let _ = router.send(message);
+        }
+    };
+
+    let result = node.vm.run_with_io(&mut input, &mut output);
+    node.waiting = result == Ok(RunResult::NeedInput);
+    node.halted = result == Ok(RunResult::Stopped);
+    activity.finish(address);
+
+    // stopped by the router, so it isn't listening
+    if !stopped {
+        let _ = router.send(Message::Done(address, result));
+    }
+    receiver
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::intcode::asm::assemble;
+
+    // Passes every packet on to the next address, adding 1 to y
+    const RELAY: &str = "
+              IN [addr]
+        loop: IN [x]
+              EQ [x], #-1, [t]
+              JT [t], #loop
+              IN [y]
+              ADD [addr], #1, [dest]
+              ADD [y], #1, [y]
+              OUT [dest]
+              OUT [x]
+              OUT [y]
+              JT #1, #loop
+        addr: DATA 0
+        dest: DATA 0
+        x:    DATA 0
+        y:    DATA 0
+        t:    DATA 0
+    ";
+
+    // Starts a packet at node 0 each time the network goes idle, a few times
+    struct Starter {
+        starts: i64,
+        received: Vec<Packet>,
+    }
+
+    impl Hook for Starter {
+        fn receive(&mut self, packet: Packet) -> Control {
+            self.received.push(packet);
+            Control::Continue
+        }
+
+        fn idle(&mut self) -> Option<Packet> {
+            if self.starts == 0 {
+                return None;
+            }
+            self.starts -= 1;
+            Some(Packet {
+                dest: 0,
+                x: self.starts,
+                y: 100 * self.starts,
+            })
+        }
+    }
+
+    fn expected() -> Vec<Packet> {
+        (0..3)
+            .rev()
+            .map(|x| Packet {
+                dest: 4,
+                x,
+                y: 100 * x + 4,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn relay() {
+        let code = assemble(RELAY).unwrap();
+        let mut hook = Starter {
+            starts: 3,
+            received: Vec::new(),
+        };
+
+        let mut network = Network::new(&code, 4).with_hook_address(4);
+        assert_eq!(network.run(&mut hook), Ok(NetworkResult::Idle));
+        assert_eq!(hook.received, expected());
+    }
+
+    #[test]
+    fn relay_threaded() {
+        let code = assemble(RELAY).unwrap();
+
+        for &empty_queue in &[EmptyQueue::Feed(-1), EmptyQueue::Wait] {
+            let mut hook = Starter {
+                starts: 3,
+                received: Vec::new(),
+            };
+
+            let mut network = Network::new(&code, 4)
+                .with_hook_address(4)
+                .with_empty_queue(empty_queue);
+            assert_eq!(network.run_threaded(&mut hook), Ok(NetworkResult::Idle));
+            assert_eq!(hook.received, expected());
+
+            // the network is left as the threads had it, so it can carry on
+            let mut hook = Starter {
+                starts: 3,
+                received: Vec::new(),
+            };
+            assert_eq!(network.run(&mut hook), Ok(NetworkResult::Idle));
+            assert_eq!(hook.received, expected());
+        }
+    }
+
+    // Reads -1, works for a while, and only then sends (1, 2) to 9
+    const SLOW: &str = "
+              IN [x]
+              IN [x]
+        spin: ADD [n], #-1, [n]
+              JT [n], #spin
+              OUT #9
+              OUT #1
+              OUT #2
+        idle: IN [x]
+              JT #1, #idle
+        x:    DATA 0
+        n:    DATA 300000
+    ";
+
+    #[test]
+    fn idle_only_once_fed_and_back_for_more() {
+        struct Collect(Vec<Packet>);
+        impl Hook for Collect {
+            fn receive(&mut self, packet: Packet) -> Control {
+                self.0.push(packet);
+                Control::Continue
+            }
+        }
+
+        let code = assemble(SLOW).unwrap();
+        let sent = Packet {
+            dest: 9,
+            x: 1,
+            y: 2,
+        };
+        let mut network = Network::new(&code, 1).with_hook_address(9);
+        let mut hook = Collect(Vec::new());
+        assert_eq!(network.run_threaded(&mut hook), Ok(NetworkResult::Idle));
+        assert_eq!(hook.0, vec![sent]);
+    }
+
+    #[test]
+    fn printer_as_nat() {
+        let packet = Packet {
+            dest: 255,
+            x: 3,
+            y: 4,
+        };
+
+        let mut printer = NetworkPrinter::new(Vec::new(), true);
+        assert_eq!(printer.idle(), None);
+        assert_eq!(printer.receive(packet), Control::Continue);
+        assert_eq!(printer.idle(), Some(Packet { dest: 0, ..packet }));
+        assert_eq!(printer.receive(packet), Control::Continue);
+        assert_eq!(printer.idle(), None);
+        assert!(printer.finish().is_ok());
+        assert_eq!(
+            String::from_utf8(printer.out).unwrap(),
+            "Network idle\nPacket to 255: x 3, y 4\nNetwork idle\n\
+             Packet to 255: x 3, y 4\nNetwork idle\nResent y 4 twice in a row\n"
+        );
+
+        // without the NAT, idle just stops the network
+        let mut printer = NetworkPrinter::new(Vec::new(), false);
+        printer.receive(packet);
+        assert_eq!(printer.idle(), None);
+    }
+
+    #[test]
+    fn hook_can_stop() {
+        struct StopAtOnce;
+        impl Hook for StopAtOnce {
+            fn receive(&mut self, _packet: Packet) -> Control {
+                Control::Stop
+            }
+        }
+
+        // sends (1, 2) to 9 and halts
+        let code = [104, 9, 104, 1, 104, 2, 99];
+        let mut network = Network::new(&code, 2).with_hook_address(9);
+        assert_eq!(network.run(&mut StopAtOnce), Ok(NetworkResult::Stopped));
+
+        let mut network = Network::new(&code, 2).with_hook_address(9);
+        assert_eq!(
+            network.run_threaded(&mut StopAtOnce),
+            Ok(NetworkResult::Stopped)
+        );
+    }
+
+    #[test]
+    fn halting_and_errors() {
+        let code = [104, 1, 104, 1, 104, 2, 99];
+        let mut network = Network::new(&code, 2);
+        assert_eq!(network.run(&mut ()), Ok(NetworkResult::Halted));
+        let mut network = Network::new(&code, 2);
+        assert_eq!(network.run_threaded(&mut ()), Ok(NetworkResult::Halted));
+
+        let bad = Packet {
+            dest: 5,
+            x: 1,
+            y: 2,
+        };
+        let code = [104, 5, 104, 1, 104, 2, 99];
+        let mut network = Network::new(&code, 2);
+        assert_eq!(network.run(&mut ()), Err(NetworkError::UnknownAddress(bad)));
+        let mut network = Network::new(&code, 2);
+        assert_eq!(
+            network.run_threaded(&mut ()),
+            Err(NetworkError::UnknownAddress(bad))
+        );
+
+        let code = [3, 0, 42];
+        let mut network = Network::new(&code, 1);
+        match network.run(&mut ()) {
+            Err(NetworkError::Fault { address: 0, error }) => assert_eq!(error.ip(), 2),
+            other => panic!("Expected a fault, got {:?}", other),
+        }
+    }
+}
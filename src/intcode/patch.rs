@@ -0,0 +1,122 @@
+use std::fmt;
+
+use super::asm::AsmError;
+
+// Words to overwrite in a program before running it, such as day 13's free play. As text,
+// one address and value per line, written like a listing:
+//
+//   ; insert coins
+//   0: 2
+//
+// Blank lines and everything after a ; are ignored. Mistakes are reported by line, as for
+// assembly.
+
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct Patch {
+    // in the order given, so a later word for the same address wins
+    words: Vec<(usize, i64)>,
+}
+
+impl Patch {
+    pub fn new() -> Self {
+        Patch::default()
+    }
+
+    pub fn with(mut self, address: usize, val: i64) -> Self {
+        self.words.push((address, val));
+        self
+    }
+
+    pub fn parse(text: &str) -> Result<Self, AsmError> {
+        let mut patch = Patch::new();
+
+        for (i, line) in text.lines().enumerate() {
+            let line = line.split(';').next().unwrap().trim();
+            if line.is_empty() {
+                continue;
+            }
+
+            let error = |message: String| AsmError {
+                line: i + 1,
+                message,
+            };
+            let (address, val) = line
+                .split_once(':')
+                .ok_or_else(|| error(format!("expected address: value, got {:?}", line)))?;
+            let address = address
+                .trim()
+                .parse()
+                .map_err(|_| error(format!("bad address {:?}", address.trim())))?;
+            let val = val
+                .trim()
+                .parse()
+                .map_err(|_| error(format!("bad value {:?}", val.trim())))?;
+
+            patch = patch.with(address, val);
+        }
+
+        Ok(patch)
+    }
+
+    pub fn words(&self) -> &[(usize, i64)] {
+        &self.words
+    }
+}
+
+impl fmt::Display for Patch {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for (address, val) in &self.words {
+            writeln!(f, "{}: {}", address, val)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::intcode::{str_to_ints, RunResult, VM};
+
+    #[test]
+    fn parse() {
+        let patch = Patch::parse("; free play\n0: 2\n\n  7 : -1 ; twice\n7: 5\n").unwrap();
+        assert_eq!(patch, Patch::new().with(0, 2).with(7, -1).with(7, 5));
+        assert_eq!(Patch::parse(&patch.to_string()), Ok(patch));
+
+        assert_eq!(
+            Patch::parse("0: 1\n3 4"),
+            Err(AsmError {
+                line: 2,
+                message: "expected address: value, got \"3 4\"".to_string()
+            })
+        );
+        assert_eq!(
+            Patch::parse("-1: 4").unwrap_err().message,
+            "bad address \"-1\""
+        );
+        assert_eq!(Patch::parse("1: x").unwrap_err().message, "bad value \"x\"");
+    }
+
+    #[test]
+    fn applied_before_running() {
+        // outputs the sum of addresses 9 and 10, then halts
+        let code = str_to_ints("1,9,10,11,4,11,99,0,0,3,4,0");
+        let patch = Patch::new().with(9, 30).with(10, 12);
+
+        let mut vm = VM::new(&code).with_patch(&patch);
+        assert_eq!(vm.run(), Ok(RunResult::Stopped));
+        assert_eq!(vm.get_all_outputs(), vec![42]);
+        assert_eq!(vm.peek_range(9..12), vec![30, 12, 42]);
+
+        // echoes inputs forever; poking the output to immediate mode, after it's already
+        // run once, outputs its address instead
+        let mut vm = VM::new(&str_to_ints("3,20,4,20,1105,1,0"));
+        vm.give_input(5);
+        assert_eq!(vm.run(), Ok(RunResult::NeedInput));
+        vm.poke(2, 104);
+        vm.give_input(7);
+        assert_eq!(vm.run(), Ok(RunResult::NeedInput));
+        assert_eq!(vm.get_all_outputs(), vec![5, 20]);
+        assert_eq!(vm.peek(20), 7);
+    }
+}
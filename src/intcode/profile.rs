@@ -0,0 +1,314 @@
+use std::collections::{HashMap, HashSet};
+use std::fmt::Write;
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use super::disasm::{mnemonic, Item};
+use super::memory::Memory;
+use super::{skip, to_op, Op, VM};
+
+// Instructions executed by any number of VMs, such as everything one solution runs.
+// Whoever runs a VM adds its steps in, and this can be shared between threads.
+#[derive(Debug, Default)]
+pub struct StepCount(AtomicU64);
+
+impl StepCount {
+    pub fn new() -> Self {
+        StepCount::default()
+    }
+
+    pub fn add(&self, steps: u64) {
+        self.0.fetch_add(steps, Ordering::Relaxed);
+    }
+
+    pub fn total(&self) -> u64 {
+        self.0.load(Ordering::Relaxed)
+    }
+}
+
+impl<M: Memory> VM<M> {
+    // Starts collecting a Profile; this slows the VM down, so it's off by default
+    pub fn enable_profiling(&mut self) {
+        if self.profile.is_none() {
+            self.profile = Some(Profile::new(self.ip));
+        }
+    }
+
+    pub fn profile(&self) -> Option<&Profile> {
+        self.profile.as_ref()
+    }
+
+    // These are counted whether or not profiling is enabled
+    pub fn total_steps(&self) -> u64 {
+        self.total_steps
+    }
+
+    pub fn last_run_steps(&self) -> u64 {
+        self.last_run_steps
+    }
+}
+
+// Counts collected while profiling is enabled on a VM
+#[derive(Clone, Debug, Default)]
+pub struct Profile {
+    pub total: u64,
+    // address -> number of times the instruction there was executed
+    pub executions: HashMap<usize, u64>,
+    // mnemonic -> number of times executed
+    pub op_counts: HashMap<&'static str, u64>,
+    // address -> number of parameter reads / writes of that address
+    pub reads: HashMap<usize, u64>,
+    pub writes: HashMap<usize, u64>,
+    // addresses control arrived at other than by falling through
+    leaders: HashSet<usize>,
+    // addresses of jumps and halts, which end a basic block
+    branches: HashSet<usize>,
+    widths: HashMap<usize, usize>,
+}
+
+// A straight run of instructions which (in this profile) were always entered at the top
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct BasicBlock {
+    pub start: usize,
+    // address of the last instruction in the block
+    pub end: usize,
+    pub num_instructions: usize,
+    pub entries: u64,
+}
+
+impl Profile {
+    pub(super) fn new(start_ip: usize) -> Self {
+        let mut out = Profile::default();
+        out.leaders.insert(start_ip);
+        out
+    }
+
+    pub(super) fn record_op(&mut self, ip: usize, op: Op, next_ip: usize) {
+        let width = skip(op);
+
+        self.total += 1;
+        *self.executions.entry(ip).or_insert(0) += 1;
+        *self.op_counts.entry(mnemonic(op)).or_insert(0) += 1;
+        self.widths.insert(ip, width);
+
+        match op {
+            Op::JumpIfTrue(_, _) | Op::JumpIfFalse(_, _) | Op::Stop => {
+                self.branches.insert(ip);
+                // the fall through and the target both start blocks
+                self.leaders.insert(next_ip);
+            }
+            _ => {
+                if next_ip != ip + width {
+                    self.leaders.insert(next_ip);
+                }
+            }
+        }
+    }
+
+    pub(super) fn record_read(&mut self, address: usize) {
+        *self.reads.entry(address).or_insert(0) += 1;
+    }
+
+    pub(super) fn record_write(&mut self, address: usize) {
+        *self.writes.entry(address).or_insert(0) += 1;
+    }
+
+    pub fn basic_blocks(&self) -> Vec<BasicBlock> {
+        let mut leaders = self
+            .leaders
+            .iter()
+            .copied()
+            .filter(|a| self.executions.contains_key(a))
+            .collect::<Vec<_>>();
+        leaders.sort();
+
+        let mut out = Vec::new();
+        for start in leaders {
+            let mut end = start;
+            let mut num_instructions = 1;
+
+            while !self.branches.contains(&end) {
+                let next = end + self.widths[&end];
+                if self.leaders.contains(&next) || !self.executions.contains_key(&next) {
+                    break;
+                }
+                end = next;
+                num_instructions += 1;
+            }
+
+            out.push(BasicBlock {
+                start,
+                end,
+                num_instructions,
+                entries: self.executions[&start],
+            });
+        }
+
+        out
+    }
+
+    // A human-readable summary, showing the top few entries of each table.
+    // The VM is used to print the instructions; pass the one that was profiled.
+    pub fn report<M: Memory>(&self, vm: &VM<M>, top: usize) -> String {
+        let mut out = String::new();
+        let share = |n: u64| 100.0 * n as f64 / self.total.max(1) as f64;
+
+        let instruction = |address: usize| match to_op(vm.peek(address)) {
+            Ok(op) => {
+                let params = (1..skip(op)).map(|i| vm.peek(address + i)).collect();
+                Item::Instruction(op, params).to_string()
+            }
+            Err(_) => Item::Data(vm.peek(address)).to_string(),
+        };
+
+        writeln!(out, "Executed {} instructions", self.total).unwrap();
+
+        writeln!(out, "\nHot spots:").unwrap();
+        writeln!(out, "  address       count   share  instruction").unwrap();
+        for (address, count) in sorted_desc(&self.executions).into_iter().take(top) {
+            writeln!(
+                out,
+                "  {:>7}  {:>10}  {:>5.1}%  {}",
+                address,
+                count,
+                share(count),
+                instruction(address)
+            )
+            .unwrap();
+        }
+
+        writeln!(out, "\nBy op:").unwrap();
+        for (op, count) in sorted_desc(&self.op_counts) {
+            writeln!(out, "  {:<4} {:>10}  {:>5.1}%", op, count, share(count)).unwrap();
+        }
+
+        let mut blocks = self.basic_blocks();
+        blocks.sort_by_key(|b| std::cmp::Reverse(b.entries * b.num_instructions as u64));
+        writeln!(out, "\nBasic blocks:").unwrap();
+        writeln!(out, "    start      end  length     entries   share").unwrap();
+        for block in blocks.into_iter().take(top) {
+            let executed = block.entries * block.num_instructions as u64;
+            writeln!(
+                out,
+                "  {:>7}  {:>7}  {:>6}  {:>10}  {:>5.1}%",
+                block.start,
+                block.end,
+                block.num_instructions,
+                block.entries,
+                share(executed)
+            )
+            .unwrap();
+        }
+
+        let mut touched = self.reads.clone();
+        for (&address, &count) in &self.writes {
+            *touched.entry(address).or_insert(0) += count;
+        }
+        writeln!(out, "\nMemory:").unwrap();
+        writeln!(out, "  address       reads      writes").unwrap();
+        for (address, _) in sorted_desc(&touched).into_iter().take(top) {
+            writeln!(
+                out,
+                "  {:>7}  {:>10}  {:>10}",
+                address,
+                self.reads.get(&address).copied().unwrap_or(0),
+                self.writes.get(&address).copied().unwrap_or(0)
+            )
+            .unwrap();
+        }
+
+        out
+    }
+}
+
+// Biggest counts first; ties broken by key, so reports are stable
+fn sorted_desc<K: Copy + Ord>(counts: &HashMap<K, u64>) -> Vec<(K, u64)> {
+    let mut out = counts.iter().map(|(&k, &v)| (k, v)).collect::<Vec<_>>();
+    out.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(&b.0)));
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::intcode::asm::assemble;
+    use crate::intcode::RunResult;
+
+    #[test]
+    fn counts_loop() {
+        let code = assemble(
+            "
+                   ADD #0, #3, [n]
+            loop:  ADD [n], #-1, [n]
+                   OUT [n]
+                   JT [n], #loop
+                   HLT
+            n:     DATA 0
+            ",
+        )
+        .unwrap();
+
+        let mut vm = VM::new(&code);
+        vm.enable_profiling();
+        assert_eq!(vm.run(), Ok(RunResult::Stopped));
+        assert_eq!(vm.get_all_outputs(), vec![2, 1, 0]);
+
+        let profile = vm.profile().unwrap();
+        assert_eq!(profile.total, 1 + 3 * 3 + 1);
+        assert_eq!(profile.executions[&0], 1);
+        assert_eq!(profile.executions[&4], 3);
+        assert_eq!(profile.executions[&10], 3);
+        assert_eq!(profile.executions.get(&1), None);
+        assert_eq!(profile.op_counts["ADD"], 4);
+        assert_eq!(profile.op_counts["HLT"], 1);
+
+        let n = 14;
+        assert_eq!(profile.reads[&n], 3 * 3);
+        assert_eq!(profile.writes[&n], 4);
+
+        assert_eq!(
+            profile.basic_blocks(),
+            vec![
+                BasicBlock {
+                    start: 0,
+                    end: 0,
+                    num_instructions: 1,
+                    entries: 1
+                },
+                BasicBlock {
+                    start: 4,
+                    end: 10,
+                    num_instructions: 3,
+                    entries: 3
+                },
+                BasicBlock {
+                    start: 13,
+                    end: 13,
+                    num_instructions: 1,
+                    entries: 1
+                },
+            ]
+        );
+
+        let report = profile.report(&vm, 3);
+        assert!(report.starts_with("Executed 11 instructions"));
+        assert!(report.contains("ADD [14], #-1, [14]"));
+    }
+
+    #[test]
+    fn counts_steps_per_run() {
+        let mut vm = VM::new(&[3, 0, 3, 0, 99]);
+
+        assert_eq!(vm.run(), Ok(RunResult::NeedInput));
+        assert_eq!(vm.last_run_steps(), 0);
+
+        vm.give_input(1);
+        assert_eq!(vm.run(), Ok(RunResult::NeedInput));
+        assert_eq!(vm.last_run_steps(), 1);
+
+        vm.give_input(1);
+        assert_eq!(vm.run(), Ok(RunResult::Stopped));
+        assert_eq!(vm.last_run_steps(), 2);
+        assert_eq!(vm.total_steps(), 3);
+        assert!(vm.profile().is_none());
+    }
+}
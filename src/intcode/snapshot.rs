@@ -0,0 +1,209 @@
+use std::io::{self, BufRead, Write};
+
+use super::memory::Memory;
+use super::VM;
+
+// A snapshot is plain text, one field per line, in this order:
+//
+//   intcode-snapshot 1
+//   ip 12
+//   relative_base 5
+//   stopped 0
+//   inputs 1,2
+//   outputs
+//   start 109,1,204,-1,...
+//   map 1000=5,1001=7
+//
+// start is the memory the program was loaded into, as it is now, and map is every nonzero
+// word after that. Lists may be empty, and map entries are sorted by address, so saving
+// the same VM twice gives the same file. Debugging state (watchpoints, tracer, history) isn't saved.
+
+const HEADER: &str = "intcode-snapshot 1";
+
+fn join<T: ToString>(vals: impl Iterator<Item = T>) -> String {
+    vals.map(|v| v.to_string()).collect::<Vec<_>>().join(",")
+}
+
+fn write_field<W: Write>(out: &mut W, name: &str, value: String) -> io::Result<()> {
+    if value.is_empty() {
+        writeln!(out, "{}", name)
+    } else {
+        writeln!(out, "{} {}", name, value)
+    }
+}
+
+fn invalid(message: String) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message)
+}
+
+impl<M: Memory> VM<M> {
+    pub fn save<W: Write>(&self, mut out: W) -> io::Result<()> {
+        let program_len = self.code.program_len();
+        let start = (0..program_len).map(|address| self.code.get(address));
+        let map = self
+            .code
+            .nonzero_words()
+            .into_iter()
+            .filter(|&(address, _)| address >= program_len);
+
+        writeln!(out, "{}", HEADER)?;
+        write_field(&mut out, "ip", self.ip.to_string())?;
+        write_field(&mut out, "relative_base", self.relative_base.to_string())?;
+        write_field(&mut out, "stopped", (self.stopped as u8).to_string())?;
+        write_field(&mut out, "inputs", join(self.stored_inputs.iter()))?;
+        write_field(&mut out, "outputs", join(self.stored_outputs.iter()))?;
+        write_field(&mut out, "start", join(start))?;
+        write_field(
+            &mut out,
+            "map",
+            join(map.map(|(a, v)| format!("{}={}", a, v))),
+        )?;
+
+        out.flush()
+    }
+
+    pub fn load<R: BufRead>(input: R) -> io::Result<Self> {
+        let mut lines = input.lines();
+
+        let header = lines.next().transpose()?.unwrap_or_default();
+        if header.trim() != HEADER {
+            return Err(invalid(format!("Not a snapshot; header is {:?}", header)));
+        }
+
+        let mut field = |name: &str| -> io::Result<String> {
+            let line = lines
+                .next()
+                .transpose()?
+                .ok_or_else(|| invalid(format!("Missing {}", name)))?;
+            let line = line.trim();
+
+            if line == name {
+                return Ok(String::new());
+            }
+            match line
+                .strip_prefix(name)
+                .and_then(|rest| rest.strip_prefix(' '))
+            {
+                Some(rest) => Ok(rest.trim().to_string()),
+                None => Err(invalid(format!("Expected {}, got {:?}", name, line))),
+            }
+        };
+
+        let ip = parse(&field("ip")?)?;
+        let relative_base = parse(&field("relative_base")?)?;
+        let stopped = parse::<u8>(&field("stopped")?)? != 0;
+        let inputs = parse_list(&field("inputs")?)?;
+        let outputs = parse_list(&field("outputs")?)?;
+        let start = parse_list(&field("start")?)?;
+
+        let mut memory = M::from_program(&start);
+        let map_field = field("map")?;
+        for entry in map_field.split(',').filter(|e| !e.is_empty()) {
+            let mut parts = entry.splitn(2, '=');
+            let address = parse(parts.next().unwrap_or(""))?;
+            let val = parse(parts.next().unwrap_or(""))?;
+            memory.set(address, val);
+        }
+
+        let mut vm = VM::with_memory(memory);
+        vm.ip = ip;
+        vm.relative_base = relative_base;
+        vm.stopped = stopped;
+        vm.stored_inputs = inputs.into_iter().collect();
+        vm.stored_outputs = outputs.into_iter().collect();
+
+        Ok(vm)
+    }
+}
+
+fn parse<T: std::str::FromStr>(s: &str) -> io::Result<T> {
+    s.trim()
+        .parse()
+        .map_err(|_| invalid(format!("Could not parse {:?}", s)))
+}
+
+fn parse_list(s: &str) -> io::Result<Vec<i64>> {
+    s.split(',').filter(|v| !v.is_empty()).map(parse).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::intcode::memory::SparseMemory;
+    use crate::intcode::{str_to_ints, RunResult, VM};
+
+    fn save_to_string(vm: &VM) -> String {
+        let mut out = Vec::new();
+        vm.save(&mut out).unwrap();
+        String::from_utf8(out).unwrap()
+    }
+
+    #[test]
+    fn small_snapshot() {
+        // out = in + 1 in a high address, then waits for more input
+        let mut vm = VM::new(&[3, 1000, 1001, 1000, 1, 1000, 4, 1000, 3, 0, 99]);
+        vm.give_input(6);
+        assert_eq!(vm.run(), Ok(RunResult::NeedInput));
+        vm.give_input(99);
+        vm.give_input(-4);
+
+        let saved = save_to_string(&vm);
+        assert_eq!(
+            saved,
+            "intcode-snapshot 1
+ip 8
+relative_base 0
+stopped 0
+inputs 99,-4
+outputs 7
+start 3,1000,1001,1000,1,1000,4,1000,3,0,99
+map 1000=7
+"
+        );
+
+        let mut loaded = VM::load(saved.as_bytes()).unwrap();
+        assert_eq!(save_to_string(&loaded), saved);
+        assert_eq!(loaded.run(), Ok(RunResult::Stopped));
+        assert_eq!(loaded.get_all_outputs(), vec![7]);
+        assert_eq!(loaded.peek(0), 99);
+    }
+
+    #[test]
+    fn resume_from_snapshot() {
+        let code = str_to_ints(include_str!("../resources/9a.txt"));
+
+        let mut vm = VM::new(&code);
+        vm.give_input(2);
+        for _ in 0..1000 {
+            vm.step().unwrap();
+        }
+
+        // loading into the other backend works just the same
+        let saved = save_to_string(&vm);
+        let mut loaded = VM::<SparseMemory>::load(saved.as_bytes()).unwrap();
+
+        assert_eq!(vm.run(), Ok(RunResult::Stopped));
+        assert_eq!(loaded.run(), Ok(RunResult::Stopped));
+        assert_eq!(loaded.get_all_outputs(), vm.get_all_outputs());
+    }
+
+    #[test]
+    fn empty_lists() {
+        let vm = VM::new(&[99]);
+
+        let saved = save_to_string(&vm);
+        assert!(saved.contains("\ninputs\noutputs\nstart 99\nmap\n"));
+
+        let loaded: VM = VM::load(saved.as_bytes()).unwrap();
+        assert_eq!(save_to_string(&loaded), saved);
+    }
+
+    #[test]
+    fn bad_snapshots() {
+        let load = |text: &str| VM::<SparseMemory>::load(text.as_bytes());
+
+        assert!(load("").is_err());
+        assert!(load("1,2,3\n").is_err());
+        assert!(load("intcode-snapshot 1\nip 0\n").is_err());
+        assert!(load("intcode-snapshot 1\nip x\n").is_err());
+    }
+}
@@ -0,0 +1,265 @@
+use std::io::{self, Write};
+
+use super::disasm::{mnemonic, Item};
+use super::Op;
+
+// Everything one executed instruction did
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct TraceEvent {
+    pub ip: usize,
+    pub op: Op,
+    // the raw parameter words, as in the program
+    pub params: Vec<i64>,
+    // each parameter that was read, after resolving its mode
+    pub reads: Vec<i64>,
+    // (address, new value)
+    pub writes: Vec<(usize, i64)>,
+    // (old, new)
+    pub relative_base_change: Option<(i64, i64)>,
+    pub input: Option<i64>,
+    pub output: Option<i64>,
+}
+
+pub trait TraceSink {
+    fn record(&mut self, event: &TraceEvent);
+
+    // Called once tracing is done; sinks that write somewhere should flush and report
+    // any errors here, since record has nowhere to send them
+    fn finish(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+// Handy for analyzing a trace in code rather than from a file
+impl TraceSink for Vec<TraceEvent> {
+    fn record(&mut self, event: &TraceEvent) {
+        self.push(event.clone());
+    }
+}
+
+// Writing is best-effort while tracing, so the first failure is saved for finish
+fn finish<W: Write>(out: &mut W, error: &mut Option<io::Error>) -> io::Result<()> {
+    if let Some(e) = error.take() {
+        return Err(e);
+    }
+    out.flush()
+}
+
+// One line per instruction, e.g.
+//    12: ADD [4], #3, [4]                reads 7, 3; [4] = 10
+pub struct TextSink<W: Write> {
+    out: W,
+    error: Option<io::Error>,
+}
+
+impl<W: Write> TextSink<W> {
+    pub fn new(out: W) -> Self {
+        TextSink { out, error: None }
+    }
+}
+
+impl<W: Write> TraceSink for TextSink<W> {
+    fn finish(&mut self) -> io::Result<()> {
+        finish(&mut self.out, &mut self.error)
+    }
+
+    fn record(&mut self, event: &TraceEvent) {
+        if self.error.is_some() {
+            return;
+        }
+
+        let mut effects = Vec::new();
+        if let Some(input) = event.input {
+            effects.push(format!("input {}", input));
+        }
+        if !event.reads.is_empty() {
+            let reads = event
+                .reads
+                .iter()
+                .map(|r| r.to_string())
+                .collect::<Vec<_>>();
+            effects.push(format!("reads {}", reads.join(", ")));
+        }
+        for (address, val) in &event.writes {
+            effects.push(format!("[{}] = {}", address, val));
+        }
+        if let Some((old, new)) = event.relative_base_change {
+            effects.push(format!("rb {} -> {}", old, new));
+        }
+        if let Some(output) = event.output {
+            effects.push(format!("output {}", output));
+        }
+
+        let instruction = Item::Instruction(event.op, event.params.clone()).to_string();
+        let line = format!(
+            "{:>5}: {:<30} {}",
+            event.ip,
+            instruction,
+            effects.join("; ")
+        );
+        let result = writeln!(self.out, "{}", line.trim_end());
+
+        if let Err(e) = result {
+            self.error = Some(e);
+        }
+    }
+}
+
+// One JSON object per line, for loading into other tools, e.g.
+// {"ip":12,"op":"ADD","params":[4,3,4],"reads":[7,3],"writes":[[4,10]]}
+// rb (as [old, new]), in and out only appear when the instruction did that.
+pub struct JsonLinesSink<W: Write> {
+    out: W,
+    error: Option<io::Error>,
+}
+
+impl<W: Write> JsonLinesSink<W> {
+    pub fn new(out: W) -> Self {
+        JsonLinesSink { out, error: None }
+    }
+}
+
+fn json_list(vals: &[i64]) -> String {
+    let vals = vals.iter().map(|v| v.to_string()).collect::<Vec<_>>();
+    format!("[{}]", vals.join(","))
+}
+
+impl<W: Write> TraceSink for JsonLinesSink<W> {
+    fn finish(&mut self) -> io::Result<()> {
+        finish(&mut self.out, &mut self.error)
+    }
+
+    fn record(&mut self, event: &TraceEvent) {
+        if self.error.is_some() {
+            return;
+        }
+
+        let writes = event
+            .writes
+            .iter()
+            .map(|&(address, val)| json_list(&[address as i64, val]))
+            .collect::<Vec<_>>();
+
+        let mut line = format!(
+            "{{\"ip\":{},\"op\":\"{}\",\"params\":{},\"reads\":{},\"writes\":[{}]",
+            event.ip,
+            mnemonic(event.op),
+            json_list(&event.params),
+            json_list(&event.reads),
+            writes.join(",")
+        );
+        if let Some((old, new)) = event.relative_base_change {
+            line.push_str(&format!(",\"rb\":{}", json_list(&[old, new])));
+        }
+        if let Some(input) = event.input {
+            line.push_str(&format!(",\"in\":{}", input));
+        }
+        if let Some(output) = event.output {
+            line.push_str(&format!(",\"out\":{}", output));
+        }
+        line.push('}');
+
+        if let Err(e) = writeln!(self.out, "{}", line) {
+            self.error = Some(e);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::intcode::{ParameterMode, RunResult, VM};
+    use std::sync::{Arc, Mutex};
+
+    // x = in; rb += 7; out x * 3
+    const PROGRAM: [i64; 12] = [3, 11, 109, 7, 1002, 11, 3, 11, 4, 11, 99, 0];
+
+    #[test]
+    fn records_events() {
+        let events = Arc::new(Mutex::new(Vec::new()));
+
+        let mut vm = VM::new(&PROGRAM);
+        vm.set_tracer(events.clone());
+        vm.give_input(5);
+
+        assert_eq!(vm.run(), Ok(RunResult::Stopped));
+
+        let events = events.lock().unwrap();
+        assert_eq!(events.len(), 5);
+
+        assert_eq!(events[0].input, Some(5));
+        assert_eq!(events[0].writes, vec![(11, 5)]);
+        assert_eq!(events[1].relative_base_change, Some((0, 7)));
+
+        let mul = &events[2];
+        assert_eq!(mul.ip, 4);
+        assert_eq!(
+            mul.op,
+            Op::Multiply(
+                ParameterMode::Position,
+                ParameterMode::Immediate,
+                ParameterMode::Position
+            )
+        );
+        assert_eq!(mul.params, vec![11, 3, 11]);
+        assert_eq!(mul.reads, vec![5, 3]);
+        assert_eq!(mul.writes, vec![(11, 15)]);
+
+        assert_eq!(events[3].output, Some(15));
+        assert_eq!(events[4].op, Op::Stop);
+    }
+
+    #[test]
+    fn waiting_for_input_is_not_traced() {
+        let events = Arc::new(Mutex::new(Vec::new()));
+
+        let mut vm = VM::new(&PROGRAM);
+        vm.set_tracer(events.clone());
+
+        assert_eq!(vm.run(), Ok(RunResult::NeedInput));
+        assert!(events.lock().unwrap().is_empty());
+    }
+
+    #[test]
+    fn sinks() {
+        let text = Arc::new(Mutex::new(TextSink::new(Vec::new())));
+        let json = Arc::new(Mutex::new(JsonLinesSink::new(Vec::new())));
+
+        let mut vm = VM::new(&PROGRAM);
+        vm.give_input(5);
+        let mut other = vm.clone();
+        vm.set_tracer(text.clone());
+        other.set_tracer(json.clone());
+
+        assert_eq!(vm.run(), Ok(RunResult::Stopped));
+        assert_eq!(other.run(), Ok(RunResult::Stopped));
+
+        let mut text = text.lock().unwrap();
+        text.finish().unwrap();
+        let expected = "    0: IN [11]                        input 5; [11] = 5
+    2: ARB #7                         reads 7; rb 0 -> 7
+    4: MUL [11], #3, [11]             reads 5, 3; [11] = 15
+    8: OUT [11]                       reads 15; output 15
+   10: HLT
+";
+        assert_eq!(String::from_utf8(text.out.clone()).unwrap(), expected);
+
+        let mut json = json.lock().unwrap();
+        json.finish().unwrap();
+        let out = String::from_utf8(json.out.clone()).unwrap();
+        let lines = out.lines().collect::<Vec<_>>();
+        assert_eq!(lines.len(), 5);
+        assert_eq!(
+            lines[0],
+            r#"{"ip":0,"op":"IN","params":[11],"reads":[],"writes":[[11,5]],"in":5}"#
+        );
+        assert_eq!(
+            lines[1],
+            r#"{"ip":2,"op":"ARB","params":[7],"reads":[7],"writes":[],"rb":[0,7]}"#
+        );
+        assert_eq!(
+            lines[3],
+            r#"{"ip":8,"op":"OUT","params":[11],"reads":[15],"writes":[],"out":15}"#
+        );
+    }
+}
@@ -0,0 +1,29 @@
+// Solutions to Advent of Code 2019, and the Intcode machine most of them run on. The
+// command line tool in main.rs is a thin layer over this.
+
+pub mod answer;
+pub mod check;
+pub mod grid;
+pub mod intcode;
+pub mod search;
+pub mod solution;
+
+#[cfg(test)]
+mod benches;
+
+pub mod day01;
+pub mod day02;
+pub mod day03;
+pub mod day04;
+pub mod day05;
+pub mod day06;
+pub mod day07;
+pub mod day08;
+pub mod day09;
+pub mod day10;
+pub mod day11;
+pub mod day12;
+pub mod day13;
+pub mod day14;
+pub mod day15;
+pub mod day16;
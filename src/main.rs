@@ -1,94 +1,587 @@
+use std::fs::{self, File};
+use std::io::{self, BufWriter, Read, Write};
+use std::ops::RangeInclusive;
+use std::panic;
 use std::process;
+use std::sync::{Arc, Mutex};
 use std::time::Instant;
 
-use clap::{App, Arg};
-
-mod intcode;
-
-mod day01;
-mod day02;
-mod day03;
-mod day04;
-mod day05;
-mod day06;
-mod day07;
-mod day08;
-mod day09;
-mod day10;
-mod day11;
-mod day12;
-mod day13;
-mod day14;
-mod day15;
-mod day16;
+use clap::{App, AppSettings, Arg, ArgMatches, SubCommand};
+
+use aoc_2019::answer::Answer;
+use aoc_2019::check::{self, Outcome};
+use aoc_2019::intcode;
+use aoc_2019::intcode::io::{InputSource, OutputSink, ReaderSource, WriterSink};
+use aoc_2019::intcode::memory::{DenseMemory, Memory, SparseMemory};
+use aoc_2019::intcode::network::{EmptyQueue, Network, NetworkPrinter};
+use aoc_2019::intcode::patch::Patch;
+use aoc_2019::intcode::trace::{JsonLinesSink, TextSink, TraceSink};
+use aoc_2019::solution::{self, Part, Solution, StepCount, SOLUTIONS};
+
+mod timing;
 
 fn main() {
+    let days = SOLUTIONS.iter().map(|s| s.day());
+    let number_help = format!(
+        "e.g. 2b for the second half of day 2; days {} to {} have solutions, and --list shows which parts",
+        days.clone().min().unwrap_or(0),
+        days.max().unwrap_or(0)
+    );
+
     let matches = App::new("Advent of Code 2019")
         .version("1.0")
+        .setting(AppSettings::SubcommandsNegateReqs)
+        .arg(
+            Arg::from_usage("-n, --number=<DAY_NUMBER>")
+                .help(&number_help)
+                .required_unless_one(&["all", "days", "list"]),
+        )
+        .arg(
+            Arg::from_usage(
+                "--input=[INPUT_FILE] 'puzzle input to use instead of the built-in one, or - for stdin'",
+            )
+            .conflicts_with_all(&["all", "days"]),
+        )
         .arg(Arg::from_usage(
-            "-n, --number=<DAY_NUMBER> 'e.g. 2b for the second half of day 2'",
+            "--all 'runs every part of every day, checking the answers'",
         ))
+        .arg(Arg::from_usage("--list 'lists the days with solutions'"))
+        .arg(
+            Arg::from_usage("--days=[DAYS] 'runs every part of some days, e.g. 3-9 or 5'")
+                .conflicts_with("all"),
+        )
+        .arg(Arg::from_usage(
+            "--expected=[ANSWERS_FILE] 'answers to check against, as lines like 8a: 2413'",
+        ))
+        .arg(
+            Arg::from_usage(
+                "--format=[FORMAT] 'how to print answers: text (the default) or json, a line per part'",
+            )
+            .conflicts_with_all(&["bench", "list"]),
+        )
+        .arg(
+            Arg::from_usage(
+                "--bench=[RUNS] 'runs the part this many times, timing parsing and solving'",
+            )
+            .conflicts_with_all(&["all", "days", "list"]),
+        )
+        .arg(
+            Arg::from_usage(
+                "--save-baseline=[BASELINE_FILE] 'saves the benchmark in this file, for --baseline'",
+            )
+            .requires("bench"),
+        )
+        .arg(
+            Arg::from_usage(
+                "--baseline=[BASELINE_FILE] 'compares the benchmark with one saved earlier'",
+            )
+            .requires("bench"),
+        )
+        .subcommand(
+            SubCommand::with_name("ascii")
+                .about(
+                    "Runs an Intcode program which talks in ASCII, sending it lines typed on stdin",
+                )
+                .arg(Arg::from_usage("<FILE> 'e.g. src/resources/9a.txt'")),
+        )
+        .subcommand(
+            SubCommand::with_name("asm")
+                .about("Assembles an Intcode program, printing it as comma-separated integers")
+                .arg(Arg::from_usage(
+                    "<FILE> 'assembly source, as printed by disasm'",
+                )),
+        )
+        .subcommand(
+            SubCommand::with_name("debug")
+                .about("Runs an Intcode program in an interactive debugger")
+                .arg(Arg::from_usage("<FILE> 'e.g. src/resources/9a.txt'")),
+        )
+        .subcommand(
+            SubCommand::with_name("disasm")
+                .about("Prints a listing of an Intcode program")
+                .arg(Arg::from_usage("<FILE> 'e.g. src/resources/9a.txt'")),
+        )
+        .subcommand(
+            SubCommand::with_name("network")
+                .about("Runs a network of copies of an Intcode program, which send each other packets")
+                .arg(Arg::from_usage("<FILE> 'e.g. src/resources/23a.txt'"))
+                .arg(Arg::from_usage(
+                    "--size=[SIZE] 'how many copies to run, each given its address first (default 50)'",
+                ))
+                .arg(Arg::from_usage(
+                    "--hook=[ADDRESS] 'prints packets sent to this address (default 255)'",
+                ))
+                .arg(Arg::from_usage(
+                    "--nat 'when the network goes idle, resends the last packet seen by the hook to \
+                     address 0, stopping once the same y is resent twice in a row'",
+                ))
+                .arg(Arg::from_usage(
+                    "--wait 'blocks programs until they get a packet, instead of giving them -1'",
+                ))
+                .arg(Arg::from_usage(
+                    "--threads 'runs each copy on its own thread'",
+                )),
+        )
+        .subcommand(
+            SubCommand::with_name("run")
+                .about("Runs an Intcode program, printing its outputs")
+                .arg(Arg::from_usage("<FILE> 'e.g. src/resources/9a.txt'"))
+                .arg(Arg::from_usage(
+                    "-i, --input=[INPUTS] 'comma-separated inputs, e.g. 1,2,3'",
+                ))
+                .arg(
+                    Arg::from_usage(
+                        "--stdin 'reads further inputs from stdin as the program asks for them'",
+                    )
+                    .conflicts_with("max-steps"),
+                )
+                .arg(Arg::from_usage(
+                    "--trace=[TRACE_FILE] 'writes every executed instruction to this file'",
+                ))
+                .arg(Arg::from_usage(
+                    "--json 'writes the trace as JSON lines instead of text'",
+                ))
+                .arg(Arg::from_usage(
+                    "--patch=[PATCH_FILE] 'overwrites words before running, from lines like 0: 2'",
+                ))
+                .arg(Arg::from_usage(
+                    "--memory=[BACKEND] 'how to store memory: dense (the default) or sparse'",
+                ))
+                .arg(Arg::from_usage(
+                    "--max-steps=[STEPS] 'gives up after executing this many instructions'",
+                ))
+                .arg(Arg::from_usage(
+                    "--profile 'prints instruction counts, hot spots and basic blocks afterwards'",
+                )),
+        )
         .get_matches();
 
-    let number = matches.value_of("number").unwrap();
+    match matches.subcommand() {
+        ("ascii", Some(sub)) => ascii(sub),
+        ("asm", Some(sub)) => asm(sub),
+        ("debug", Some(sub)) => debug(sub),
+        ("disasm", Some(sub)) => disasm(sub),
+        ("network", Some(sub)) => network(sub),
+        ("run", Some(sub)) => run(sub),
+        _ if matches.is_present("list") => {
+            if let Err(e) = list_solutions(&mut io::stdout()) {
+                eprintln!("Could not write list: {}", e);
+            }
+        }
+        _ if matches.is_present("all") => run_all(0..=u32::MAX, &matches),
+        _ if matches.is_present("days") => run_all(parse_days(&matches), &matches),
+        _ if matches.is_present("bench") => bench(&matches),
+        _ => run_day(
+            matches.value_of("number").unwrap(),
+            matches.value_of("input"),
+            parse_format(&matches),
+        ),
+    }
+}
+
+// Every registered day, for --list and for when a selection isn't found
+fn list_solutions(out: &mut dyn Write) -> io::Result<()> {
+    writeln!(out, "Days with solutions, and which parts are done:")?;
+    for solution in SOLUTIONS {
+        let parts = solution
+            .parts()
+            .iter()
+            .map(|part| part.to_string())
+            .collect::<Vec<_>>()
+            .join(", ");
+        writeln!(
+            out,
+            "{:>4}  {:<4}  {}",
+            solution.day(),
+            parts,
+            solution.name()
+        )?;
+    }
+    Ok(())
+}
+
+#[derive(Copy, Clone, Eq, PartialEq)]
+enum Format {
+    Text,
+    // a JSON object per line, one for each part run
+    Json,
+}
 
+fn parse_format(matches: &ArgMatches) -> Format {
+    match matches.value_of("format").unwrap_or("text") {
+        "text" => Format::Text,
+        "json" => Format::Json,
+        other => {
+            eprintln!("Unknown format {}; expected text or json", other);
+            process::exit(1);
+        }
+    }
+}
+
+// The selected part and the input to give it
+fn select(number: &str, input_path: Option<&str>) -> (&'static dyn Solution, Part, String) {
+    let (solution, part) = solution::find(number).unwrap_or_else(|| {
+        eprintln!("Unrecognized day combination: {}", number);
+        let _ = list_solutions(&mut io::stderr());
+        process::exit(1);
+    });
+
+    let input = match input_path {
+        Some(path) => read_input(path),
+        None => solution.input().to_string(),
+    };
+    (solution, part, input)
+}
+
+fn run_day(number: &str, input_path: Option<&str>, format: Format) {
+    let (solution, part, input) = select(number, input_path);
+
+    let steps = StepCount::new();
     let start = Instant::now();
+    let answer = solution.solve(part, &input, &steps);
+    let elapsed = start.elapsed();
+    let instructions = steps.total();
+
+    if format == Format::Json {
+        println!(
+            "{{\"day\":{},\"part\":\"{}\",{},\"elapsed_ms\":{:.3},\"instructions\":{}}}",
+            solution.day(),
+            part,
+            answer.json_fields(),
+            elapsed.as_secs_f64() * 1000.0,
+            instructions
+        );
+        return;
+    }
+
+    match answer {
+        // pictures start on their own line, so they line up
+        Answer::Bitmap(_) => println!("{}:\n{}", number, answer),
+        answer => println!("{}: {}", number, answer),
+    }
+    let elapsed_ms = elapsed.as_millis() as u64;
+    if instructions > 0 {
+        println!(
+            "Problem {} took {} ms ({} Intcode instructions)",
+            number, elapsed_ms, instructions
+        );
+    } else {
+        println!("Problem {} took {} ms", number, elapsed_ms);
+    }
+}
 
-    match number {
-        "1a" => day01::a(),
-        "1b" => day01::b(),
+fn bench(matches: &ArgMatches) {
+    let number = matches.value_of("number").unwrap();
+    let (solution, part, input) = select(number, matches.value_of("input"));
+    let runs = parse_arg(matches, "bench", 10);
+    if runs == 0 {
+        eprintln!("Need at least one run to time");
+        process::exit(1);
+    }
+    // the same spelling whatever was typed, so baselines match up
+    let selection = format!("{}{}", solution.day(), part);
 
-        "2a" => day02::a(),
-        "2b" => day02::b(),
+    let baseline = matches.value_of("baseline").map(|path| {
+        timing::find_baseline(&read_input(path), &selection).unwrap_or_else(|| {
+            eprintln!("No baseline for {} in {}", selection, path);
+            process::exit(1);
+        })
+    });
+
+    let result = timing::bench(solution, part, &input, runs);
+    print!(
+        "{}",
+        timing::report(&selection, runs, &result, baseline.as_ref())
+    );
+
+    if let Some(path) = matches.value_of("save-baseline") {
+        // other parts' baselines in the file are kept
+        let existing = fs::read_to_string(path).unwrap_or_default();
+        let updated = timing::update_baseline(&existing, &selection, &result);
+        if let Err(e) = fs::write(path, updated) {
+            eprintln!("Could not write {}: {}", path, e);
+            process::exit(1);
+        }
+    }
+}
 
-        "3a" => day03::a(),
-        "3b" => day03::b(),
+// e.g. 3-9 or 5
+fn parse_days(matches: &ArgMatches) -> RangeInclusive<u32> {
+    let days = matches.value_of("days").unwrap();
+    let (first, last) = days.split_once('-').unwrap_or((days, days));
 
-        "4a" => day04::a(),
-        "4b" => day04::b(),
+    match (first.trim().parse(), last.trim().parse()) {
+        (Ok(first), Ok(last)) => first..=last,
+        _ => {
+            eprintln!("Invalid days {}; expected e.g. 3-9 or 5", days);
+            process::exit(1);
+        }
+    }
+}
 
-        "5a" => day05::a(),
-        "5b" => day05::b(),
+// Runs every part of the registered days in that range, printing a table, and exits with an error if any answers
+// are wrong. A part which panics is only counted as wrong if it has an expected answer.
+fn run_all(days: RangeInclusive<u32>, matches: &ArgMatches) {
+    let expected = match matches.value_of("expected") {
+        Some(path) => check::parse_expected(&read_input(path)),
+        None => check::parse_expected(check::EXPECTED_ANSWERS),
+    };
+    let format = parse_format(matches);
 
-        "6a" => day06::a(),
-        "6b" => day06::b(),
+    if format == Format::Text {
+        println!("{}", check::table_header());
+    }
 
-        "7a" => day07::a(),
-        "7b" => day07::b(),
+    // panics are reported in the table instead
+    panic::set_hook(Box::new(|_| {}));
 
-        "8a" => day08::a(),
-        "8b" => day08::b(),
+    let mut wrong = 0;
+    for solution in SOLUTIONS.iter().filter(|s| days.contains(&s.day())) {
+        for &part in solution.parts() {
+            let outcome = Outcome::run(*solution, part, &expected);
+            if outcome.failed() {
+                wrong += 1;
+            }
 
-        "9a" => day09::a(),
-        "9b" => day09::b(),
+            match format {
+                Format::Text => println!("{}", outcome.table_row()),
+                Format::Json => println!("{}", outcome.json()),
+            }
+        }
+    }
 
-        "10a" => day10::a(),
-        "10b" => day10::b(),
+    if wrong > 0 {
+        eprintln!("{} wrong answer(s)", wrong);
+        process::exit(1);
+    }
+}
 
-        "11a" => day11::a(),
-        "11b" => day11::b(),
+// A path, or - for stdin
+fn read_input(path: &str) -> String {
+    let result = if path == "-" {
+        let mut text = String::new();
+        io::stdin().read_to_string(&mut text).map(|_| text)
+    } else {
+        fs::read_to_string(path)
+    };
 
-        "12a" => day12::a(),
-        "12b" => day12::b(),
+    result.unwrap_or_else(|e| {
+        eprintln!("Could not read {}: {}", path, e);
+        process::exit(1);
+    })
+}
 
-        "13a" => day13::a(),
-        "13b" => day13::b(),
+fn read_file(matches: &ArgMatches) -> String {
+    let path = matches.value_of("FILE").unwrap();
 
-        "14a" => day14::a(),
-        "14b" => day14::b(),
+    match fs::read_to_string(path) {
+        Ok(text) => text,
+        Err(e) => {
+            eprintln!("Could not read {}: {}", path, e);
+            process::exit(1);
+        }
+    }
+}
 
-        "15a" => day15::a(),
-        "15b" => day15::b(),
+fn read_program(matches: &ArgMatches) -> Vec<i64> {
+    intcode::str_to_ints(&read_file(matches))
+}
 
-        "16a" => day16::a(),
-        "16b" => day16::b(),
+fn ascii(matches: &ArgMatches) {
+    let code = read_program(matches);
+    let mut vm = intcode::ascii::AsciiVm::new(&code);
 
-        _ => {
-            eprintln!("Unrecognized day combination: {}", number);
+    let stdin = io::stdin();
+    let stdout = io::stdout();
+    if let Err(e) = vm.interact(stdin.lock(), stdout.lock()) {
+        eprintln!("Terminal I/O failed: {}", e);
+        process::exit(1);
+    }
+}
+
+fn asm(matches: &ArgMatches) {
+    let source = read_file(matches);
+
+    match intcode::asm::assemble(&source) {
+        Ok(code) => {
+            let words = code.iter().map(|n| n.to_string()).collect::<Vec<_>>();
+            println!("{}", words.join(","));
+        }
+        Err(e) => {
+            eprintln!(
+                "Could not assemble {}: {}",
+                matches.value_of("FILE").unwrap(),
+                e
+            );
+            process::exit(1);
+        }
+    }
+}
+
+fn debug(matches: &ArgMatches) {
+    let code = read_program(matches);
+    let mut debugger = intcode::debugger::Debugger::new(intcode::VM::new(&code));
+
+    let stdin = io::stdin();
+    let stdout = io::stdout();
+    if let Err(e) = debugger.repl(stdin.lock(), stdout.lock()) {
+        eprintln!("Debugger I/O failed: {}", e);
+        process::exit(1);
+    }
+}
+
+fn disasm(matches: &ArgMatches) {
+    let code = read_program(matches);
+    print!("{}", intcode::disasm::to_listing(&code));
+}
+
+fn parse_arg(matches: &ArgMatches, name: &str, default: usize) -> usize {
+    match matches.value_of(name) {
+        Some(val) => val.parse().unwrap_or_else(|_| {
+            eprintln!("Invalid {}: {}", name, val);
+            process::exit(1);
+        }),
+        None => default,
+    }
+}
+
+fn network(matches: &ArgMatches) {
+    let code = read_program(matches);
+    let size = parse_arg(matches, "size", 50);
+    let hook_address = parse_arg(matches, "hook", 255);
+
+    let mut network = Network::new(&code, size).with_hook_address(hook_address);
+    if matches.is_present("wait") {
+        network = network.with_empty_queue(EmptyQueue::Wait);
+    }
+
+    let mut printer = NetworkPrinter::new(io::stdout(), matches.is_present("nat"));
+    let result = if matches.is_present("threads") {
+        network.run_threaded(&mut printer)
+    } else {
+        network.run(&mut printer)
+    };
+    if let Err(e) = printer.finish() {
+        eprintln!("Could not write to stdout: {}", e);
+        process::exit(1);
+    }
+
+    match result {
+        Ok(result) => println!("Network finished: {:?}", result),
+        Err(e) => {
+            eprintln!("{}", e);
+            process::exit(1);
+        }
+    }
+}
+
+fn run(matches: &ArgMatches) {
+    let code = read_program(matches);
+    let patch = match matches.value_of("patch") {
+        Some(path) => Patch::parse(&read_input(path)).unwrap_or_else(|e| {
+            eprintln!("Could not read patch {}: {}", path, e);
+            process::exit(1);
+        }),
+        None => Patch::new(),
+    };
+
+    match matches.value_of("memory").unwrap_or("dense") {
+        "dense" => run_vm(
+            matches,
+            intcode::VM::with_memory(DenseMemory::from_program(&code)).with_patch(&patch),
+        ),
+        "sparse" => run_vm(
+            matches,
+            intcode::VM::with_memory(SparseMemory::from_program(&code)).with_patch(&patch),
+        ),
+        other => {
+            eprintln!("Unknown memory backend {}; expected dense or sparse", other);
             process::exit(1);
         }
     }
+}
+
+fn run_vm<M: Memory>(matches: &ArgMatches, mut vm: intcode::VM<M>) {
+    if let Some(inputs) = matches.value_of("input") {
+        for input in intcode::str_to_ints(inputs) {
+            vm.give_input(input);
+        }
+    }
+
+    let tracer = matches.value_of("trace").map(|path| {
+        let file = match File::create(path) {
+            Ok(file) => BufWriter::new(file),
+            Err(e) => {
+                eprintln!("Could not create {}: {}", path, e);
+                process::exit(1);
+            }
+        };
+
+        let tracer: Arc<Mutex<dyn TraceSink + Send>> = if matches.is_present("json") {
+            Arc::new(Mutex::new(JsonLinesSink::new(file)))
+        } else {
+            Arc::new(Mutex::new(TextSink::new(file)))
+        };
+        vm.set_tracer(tracer.clone());
+        tracer
+    });
+
+    if matches.is_present("profile") {
+        vm.enable_profiling();
+    }
+
+    let mut output = WriterSink::new(io::stdout());
+
+    let result = if matches.is_present("stdin") {
+        let stdin = io::stdin();
+        let mut input = ReaderSource::new(stdin.lock());
+        let result = vm.run_with_io(&mut input, &mut output);
+        if let Err(e) = input.finish() {
+            eprintln!("Could not read input: {}", e);
+        }
+        result
+    } else {
+        match matches.value_of("max-steps") {
+            Some(steps) => match steps.parse() {
+                Ok(steps) => vm.run_with_limit(steps),
+                Err(_) => {
+                    eprintln!("Invalid step count {}", steps);
+                    process::exit(1);
+                }
+            },
+            None => vm.run(),
+        }
+    };
+
+    for val in vm.get_all_outputs() {
+        output.send(val);
+    }
+    if let Err(e) = output.finish() {
+        eprintln!("Could not write output: {}", e);
+    }
+
+    if let Some(tracer) = tracer {
+        if let Err(e) = tracer.lock().unwrap().finish() {
+            eprintln!("Could not write trace: {}", e);
+        }
+    }
 
-    let elapsed_ms = start.elapsed().as_millis() as u64;
-    println!("Problem {} took {} ms", number, elapsed_ms);
+    if let Some(profile) = vm.profile() {
+        eprint!("{}", profile.report(&vm, 10));
+    } else {
+        eprintln!("Executed {} instructions", vm.last_run_steps());
+    }
+
+    match result {
+        Ok(intcode::RunResult::Stopped) => {}
+        Ok(intcode::RunResult::OutOfFuel) => {
+            eprintln!("Program was still running at ip {}", vm.ip());
+            process::exit(1);
+        }
+        Ok(other) => eprintln!("Program paused: {:?}", other),
+        Err(e) => {
+            eprintln!("Program faulted: {}", e);
+            process::exit(1);
+        }
+    }
 }
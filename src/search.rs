@@ -0,0 +1,173 @@
+use std::thread;
+
+// Every ordering of 0..n, in lexicographic order
+pub struct Permutations {
+    next: Option<Vec<usize>>,
+}
+
+fn factorial(n: usize) -> u64 {
+    (1..=n as u64).fold(1, |acc, k| {
+        acc.checked_mul(k)
+            .unwrap_or_else(|| panic!("{}! permutations is too many to search", n))
+    })
+}
+
+impl Permutations {
+    // Starts rank permutations in; working this out directly means threads can each take
+    // a range without generating what comes before
+    pub fn from_rank(n: usize, mut rank: u64) -> Self {
+        if rank >= factorial(n) {
+            return Permutations { next: None };
+        }
+
+        let mut unused = (0..n).collect::<Vec<_>>();
+        let mut first = Vec::with_capacity(n);
+        for i in (0..n).rev() {
+            let block = factorial(i);
+            first.push(unused.remove((rank / block) as usize));
+            rank %= block;
+        }
+
+        Permutations { next: Some(first) }
+    }
+}
+
+// Rearranges into the next permutation in lexicographic order, or returns false if this was
+// the last one
+fn advance(perm: &mut [usize]) -> bool {
+    let pivot = match (1..perm.len()).rev().find(|&i| perm[i - 1] < perm[i]) {
+        Some(i) => i - 1,
+        None => return false,
+    };
+
+    let successor = (pivot + 1..perm.len())
+        .rev()
+        .find(|&i| perm[i] > perm[pivot])
+        .unwrap();
+    perm.swap(pivot, successor);
+    perm[pivot + 1..].reverse();
+    true
+}
+
+impl Iterator for Permutations {
+    type Item = Vec<usize>;
+
+    fn next(&mut self) -> Option<Vec<usize>> {
+        let current = self.next.take()?;
+
+        let mut next = current.clone();
+        if advance(&mut next) {
+            self.next = Some(next);
+        }
+        Some(current)
+    }
+}
+
+// Tries every ordering of items, spread across a thread per core, and returns the one
+// scoring highest along with its score. Ties go to whichever ordering comes first.
+pub fn best_permutation<T, S, F>(items: &[T], eval: F) -> (Vec<T>, S)
+where
+    T: Clone + Sync,
+    S: Ord + Send,
+    F: Fn(&[T]) -> S + Sync,
+{
+    let total = factorial(items.len());
+    let threads = thread::available_parallelism().map_or(1, |n| n.get()) as u64;
+    let per_thread = (total + threads - 1) / threads;
+
+    let best_in_range = |start: u64| {
+        let mut best: Option<(Vec<usize>, S)> = None;
+        for perm in Permutations::from_rank(items.len(), start).take(per_thread as usize) {
+            let arranged = perm.iter().map(|&i| items[i].clone()).collect::<Vec<_>>();
+            let score = eval(&arranged);
+            if best
+                .as_ref()
+                .map_or(true, |(_, best_score)| score > *best_score)
+            {
+                best = Some((perm, score));
+            }
+        }
+        best
+    };
+
+    let best = thread::scope(|scope| {
+        let workers = (0..threads)
+            .map(|t| t * per_thread)
+            .filter(|&start| start < total)
+            .map(|start| scope.spawn(move || best_in_range(start)))
+            .collect::<Vec<_>>();
+
+        // ranges are in order, so keeping the earlier of two equal scores keeps ties stable
+        workers
+            .into_iter()
+            .filter_map(|worker| worker.join().unwrap())
+            .fold(
+                None,
+                |best: Option<(Vec<usize>, S)>, (perm, score)| match best {
+                    Some(best) if best.1 >= score => Some(best),
+                    _ => Some((perm, score)),
+                },
+            )
+    });
+
+    let (perm, score) = best.unwrap();
+    (perm.iter().map(|&i| items[i].clone()).collect(), score)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn lexicographic_order() {
+        let all = Permutations::from_rank(3, 0).collect::<Vec<_>>();
+        assert_eq!(
+            all,
+            vec![
+                vec![0, 1, 2],
+                vec![0, 2, 1],
+                vec![1, 0, 2],
+                vec![1, 2, 0],
+                vec![2, 0, 1],
+                vec![2, 1, 0],
+            ]
+        );
+
+        let all = Permutations::from_rank(6, 0).collect::<Vec<_>>();
+        assert_eq!(all.len(), 720);
+        for (rank, perm) in all.iter().enumerate() {
+            assert_eq!(
+                Permutations::from_rank(6, rank as u64).next().as_ref(),
+                Some(perm)
+            );
+        }
+        assert_eq!(Permutations::from_rank(6, 720).next(), None);
+
+        assert_eq!(
+            Permutations::from_rank(0, 0).collect::<Vec<_>>(),
+            vec![vec![]]
+        );
+    }
+
+    #[test]
+    fn finds_the_best() {
+        // weights later positions more, so descending order wins
+        let score = |items: &[i64]| {
+            items
+                .iter()
+                .enumerate()
+                .map(|(i, &v)| (i as i64 + 1) * -v)
+                .sum::<i64>()
+        };
+        assert_eq!(
+            best_permutation(&[3, 1, 4, 5, 9, 2, 6], score),
+            (vec![9, 6, 5, 4, 3, 2, 1], -86)
+        );
+
+        // every ordering ties, so the first one wins
+        assert_eq!(
+            best_permutation(&['a', 'b', 'c'], |_| 0),
+            (vec!['a', 'b', 'c'], 0)
+        );
+    }
+}
@@ -0,0 +1,120 @@
+use std::fmt;
+
+use crate::answer::Answer;
+pub use crate::intcode::profile::StepCount;
+use crate::{
+    day01, day02, day03, day04, day05, day06, day07, day08, day09, day10, day11, day12, day13,
+    day14, day15, day16,
+};
+
+// One day's puzzle; each day module has one of these, and registers it in SOLUTIONS
+pub trait Solution: Sync {
+    fn day(&self) -> u32;
+
+    // The puzzle's title
+    fn name(&self) -> &'static str;
+
+    // Built in, for when no other input is given
+    fn input(&self) -> &'static str;
+
+    // Just the parsing both parts start with, so benchmarks can time it on its own
+    fn parse(&self, _input: &str) {}
+
+    // Days which run Intcode add the instructions they execute to steps
+    fn a(&self, input: &str, steps: &StepCount) -> Answer;
+
+    fn b(&self, input: &str, steps: &StepCount) -> Answer;
+
+    // The parts with a solution; selecting any other part finds nothing
+    fn parts(&self) -> &'static [Part] {
+        &Part::BOTH
+    }
+
+    fn solve(&self, part: Part, input: &str, steps: &StepCount) -> Answer {
+        match part {
+            Part::A => self.a(input, steps),
+            Part::B => self.b(input, steps),
+        }
+    }
+}
+
+#[derive(Debug, Eq, PartialEq, Copy, Clone, Hash)]
+pub enum Part {
+    A,
+    B,
+}
+
+impl Part {
+    pub const BOTH: [Part; 2] = [Part::A, Part::B];
+}
+
+impl fmt::Display for Part {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Part::A => f.pad("a"),
+            Part::B => f.pad("b"),
+        }
+    }
+}
+
+// In order of day
+pub static SOLUTIONS: &[&dyn Solution] = &[
+    &day01::Day01,
+    &day02::Day02,
+    &day03::Day03,
+    &day04::Day04,
+    &day05::Day05,
+    &day06::Day06,
+    &day07::Day07,
+    &day08::Day08,
+    &day09::Day09,
+    &day10::Day10,
+    &day11::Day11,
+    &day12::Day12,
+    &day13::Day13,
+    &day14::Day14,
+    &day15::Day15,
+    &day16::Day16,
+];
+
+// A selection like 2b
+pub fn find(selection: &str) -> Option<(&'static dyn Solution, Part)> {
+    let selection = selection.trim();
+    let (day, part) = match selection.char_indices().last()? {
+        (i, 'a') => (&selection[..i], Part::A),
+        (i, 'b') => (&selection[..i], Part::B),
+        _ => return None,
+    };
+
+    let day = day.parse().ok()?;
+    let solution = SOLUTIONS.iter().find(|solution| solution.day() == day)?;
+    if !solution.parts().contains(&part) {
+        return None;
+    }
+    Some((*solution, part))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn registered_in_order() {
+        let days = SOLUTIONS.iter().map(|s| s.day()).collect::<Vec<_>>();
+        assert_eq!(days, (1..=SOLUTIONS.len() as u32).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn find_selections() {
+        let (solution, part) = find("12b").unwrap();
+        assert_eq!(solution.day(), 12);
+        assert_eq!(part, Part::B);
+        assert_eq!(solution.name(), "The N-Body Problem");
+
+        assert_eq!(find("16a").unwrap().0.parts(), &[Part::A]);
+
+        for bad in &["", "b", "12", "12c", "99a", "-1a", "16b"] {
+            assert!(find(bad).is_none(), "{:?}", bad);
+        }
+    }
+}
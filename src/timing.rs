@@ -0,0 +1,247 @@
+use std::collections::BTreeMap;
+use std::fmt::Write;
+use std::hint::black_box;
+use std::time::Instant;
+
+use aoc_2019::solution::{Part, Solution, StepCount};
+
+// Untimed runs first, so caches and the allocator have settled
+const WARM_UP_RUNS: usize = 3;
+
+// A summary of some timings, all in microseconds
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Stats {
+    pub min: f64,
+    pub median: f64,
+    pub mean: f64,
+    pub stddev: f64,
+}
+
+impl Stats {
+    pub fn from_samples(samples: &[f64]) -> Self {
+        assert!(!samples.is_empty(), "No samples to summarize");
+
+        let mut sorted = samples.to_vec();
+        sorted.sort_by(|a, b| a.partial_cmp(b).unwrap());
+
+        let n = sorted.len();
+        let median = if n % 2 == 1 {
+            sorted[n / 2]
+        } else {
+            (sorted[n / 2 - 1] + sorted[n / 2]) / 2.0
+        };
+        let mean = sorted.iter().sum::<f64>() / n as f64;
+        let variance = if n > 1 {
+            sorted.iter().map(|s| (s - mean).powi(2)).sum::<f64>() / (n - 1) as f64
+        } else {
+            0.0
+        };
+
+        Stats {
+            min: sorted[0],
+            median,
+            mean,
+            stddev: variance.sqrt(),
+        }
+    }
+
+    fn values(&self) -> [f64; 4] {
+        [self.min, self.median, self.mean, self.stddev]
+    }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Bench {
+    pub parse: Stats,
+    // The rest of the part, after parsing
+    pub solve: Stats,
+}
+
+fn micros_since(start: Instant) -> f64 {
+    start.elapsed().as_secs_f64() * 1_000_000.0
+}
+
+// Times the part runs times over. Solutions parse as they go, so each run times the
+// parsing by itself and then the whole part, and counts the difference as solving.
+pub fn bench(solution: &dyn Solution, part: Part, input: &str, runs: usize) -> Bench {
+    for _ in 0..WARM_UP_RUNS {
+        black_box(solution.solve(part, input, &StepCount::new()));
+    }
+
+    let mut parse = Vec::with_capacity(runs);
+    let mut solve = Vec::with_capacity(runs);
+    for _ in 0..runs {
+        let start = Instant::now();
+        solution.parse(input);
+        let parse_us = micros_since(start);
+
+        let start = Instant::now();
+        black_box(solution.solve(part, input, &StepCount::new()));
+        let total_us = micros_since(start);
+
+        parse.push(parse_us);
+        solve.push((total_us - parse_us).max(0.0));
+    }
+
+    Bench {
+        parse: Stats::from_samples(&parse),
+        solve: Stats::from_samples(&solve),
+    }
+}
+
+// Baselines are plain text, a line per part and stage, e.g.
+//
+//   12b parse 10.2 10.9 11.0 0.4
+//   12b solve 37012.5 37390.1 37455.0 310.2
+//
+// with min, median, mean and stddev in that order. A file can hold any number of parts.
+
+type Baselines = BTreeMap<(String, String), Stats>;
+
+fn parse_baselines(text: &str) -> Baselines {
+    text.lines()
+        .filter_map(|line| {
+            let fields = line.split_whitespace().collect::<Vec<_>>();
+            if fields.len() != 6 {
+                return None;
+            }
+
+            let vals = fields[2..]
+                .iter()
+                .map(|f| f.parse().ok())
+                .collect::<Option<Vec<f64>>>()?;
+            let stats = Stats {
+                min: vals[0],
+                median: vals[1],
+                mean: vals[2],
+                stddev: vals[3],
+            };
+            Some(((fields[0].to_string(), fields[1].to_string()), stats))
+        })
+        .collect()
+}
+
+pub fn find_baseline(text: &str, selection: &str) -> Option<Bench> {
+    let baselines = parse_baselines(text);
+    let stage = |name: &str| baselines.get(&(selection.to_string(), name.to_string()));
+
+    Some(Bench {
+        parse: *stage("parse")?,
+        solve: *stage("solve")?,
+    })
+}
+
+// The baseline file with this part's entry added, or replaced
+pub fn update_baseline(text: &str, selection: &str, bench: &Bench) -> String {
+    let mut baselines = parse_baselines(text);
+    for (name, stats) in &[("parse", bench.parse), ("solve", bench.solve)] {
+        baselines.insert((selection.to_string(), name.to_string()), *stats);
+    }
+
+    let mut out = String::new();
+    for ((selection, name), stats) in baselines {
+        let vals = stats.values();
+        writeln!(
+            out,
+            "{} {} {:.1} {:.1} {:.1} {:.1}",
+            selection, name, vals[0], vals[1], vals[2], vals[3]
+        )
+        .unwrap();
+    }
+    out
+}
+
+// A table of the timings, with how much each changed since the baseline if there is one
+pub fn report(selection: &str, runs: usize, bench: &Bench, baseline: Option<&Bench>) -> String {
+    let mut out = String::new();
+    writeln!(
+        out,
+        "{}: {} runs after {} to warm up, in µs",
+        selection, runs, WARM_UP_RUNS
+    )
+    .unwrap();
+    writeln!(
+        out,
+        "{:8}{:>12}{:>12}{:>12}{:>12}",
+        "", "min", "median", "mean", "stddev"
+    )
+    .unwrap();
+
+    let stages = [("parse", bench.parse), ("solve", bench.solve)];
+    for (i, (name, stats)) in stages.iter().enumerate() {
+        write!(out, "{:8}", name).unwrap();
+        for val in &stats.values() {
+            write!(out, "{:>12.1}", val).unwrap();
+        }
+        writeln!(out).unwrap();
+
+        if let Some(baseline) = baseline {
+            let old = [baseline.parse, baseline.solve][i];
+            write!(out, "{:8}", "  change").unwrap();
+            for (new, old) in stats.values().iter().zip(&old.values()) {
+                let change = if *old > 0.0 {
+                    format!("{:+.1}%", (new - old) / old * 100.0)
+                } else {
+                    "-".to_string()
+                };
+                write!(out, "{:>12}", change).unwrap();
+            }
+            writeln!(out).unwrap();
+        }
+    }
+
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn stats(min: f64, median: f64, mean: f64, stddev: f64) -> Stats {
+        Stats {
+            min,
+            median,
+            mean,
+            stddev,
+        }
+    }
+
+    #[test]
+    fn summarize() {
+        let s = Stats::from_samples(&[4.0, 1.0, 3.0, 2.0]);
+        assert_eq!((s.min, s.median, s.mean), (1.0, 2.5, 2.5));
+        assert!((s.stddev - 1.2910).abs() < 0.0001);
+
+        let s = Stats::from_samples(&[7.0]);
+        assert_eq!(s, stats(7.0, 7.0, 7.0, 0.0));
+    }
+
+    #[test]
+    fn baselines() {
+        let first = Bench {
+            parse: stats(1.0, 2.0, 3.0, 0.5),
+            solve: stats(100.0, 200.0, 300.0, 10.0),
+        };
+        let text = update_baseline("", "9b", &first);
+        assert_eq!(
+            text,
+            "9b parse 1.0 2.0 3.0 0.5\n9b solve 100.0 200.0 300.0 10.0\n"
+        );
+
+        // other parts are kept, and this one's replaced
+        let text = update_baseline(&text, "1a", &first);
+        let second = Bench {
+            parse: stats(1.0, 1.0, 1.0, 0.0),
+            ..first
+        };
+        let text = update_baseline(&text, "9b", &second);
+        assert_eq!(find_baseline(&text, "1a"), Some(first));
+        assert_eq!(find_baseline(&text, "9b"), Some(second));
+        assert_eq!(find_baseline(&text, "2a"), None);
+        assert_eq!(text.lines().count(), 4);
+
+        let report = report("9b", 10, &first, Some(&second));
+        assert!(report.contains("parse            1.0         2.0         3.0         0.5"));
+        assert!(report.contains("  change       +0.0%     +100.0%     +200.0%           -"));
+    }
+}
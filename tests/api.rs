@@ -0,0 +1,56 @@
+// The library as other tools see it, from outside the crate
+
+use aoc_2019::answer::Answer;
+use aoc_2019::grid::{Bounds, Direction, Pos};
+use aoc_2019::intcode::{str_to_ints, RunResult, VM};
+use aoc_2019::solution::{self, Part, StepCount, SOLUTIONS};
+use aoc_2019::{day01, day06};
+
+#[test]
+fn intcode() {
+    // adds its two inputs
+    let code = str_to_ints("3,11,3,12,1,11,12,13,4,13,99,0,0,0");
+    let mut vm = VM::new(&code);
+    vm.give_input(20);
+    vm.give_input(22);
+
+    assert_eq!(vm.run(), Ok(RunResult::Stopped));
+    assert_eq!(vm.get_all_outputs(), vec![42]);
+}
+
+#[test]
+fn solvers() {
+    assert_eq!(day01::solve_a("12\n1969"), Answer::Int(2 + 654));
+    assert_eq!(
+        day06::solve_a("COM)B\nB)C\nC)D\nD)E\nE)F\nB)G\nG)H\nD)I\nE)J\nJ)K\nK)L"),
+        Answer::Int(42)
+    );
+
+    let steps = StepCount::new();
+    let (day, part) = solution::find("1b").unwrap();
+    assert_eq!(
+        day.solve(part, day.input(), &steps),
+        day01::solve_b(day01::INPUT)
+    );
+    assert_eq!(part, Part::B);
+    assert_eq!(SOLUTIONS.len(), 16);
+
+    // only Intcode days count steps, and each run adds to them
+    assert_eq!(steps.total(), 0);
+    let (day, part) = solution::find("9a").unwrap();
+    day.solve(part, day.input(), &steps);
+    let once = steps.total();
+    assert!(once > 0);
+    day.solve(part, day.input(), &steps);
+    assert_eq!(steps.total(), 2 * once);
+}
+
+#[test]
+fn grid() {
+    let path = [Direction::Right, Direction::Right, Direction::Down];
+    let end = path.iter().fold(Pos::ORIGIN, |pos, &dir| pos.step(dir));
+    assert_eq!(end, Pos::new(2, 1));
+
+    let bounds = Bounds::of(vec![Pos::ORIGIN, end]).unwrap();
+    assert_eq!(bounds.bitmap(|pos| pos == end)[1], vec![false, false, true]);
+}